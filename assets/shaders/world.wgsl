@@ -13,16 +13,44 @@ struct Curve {
     end: vec2<f32>,
     radius: f32,
     length: f32,
-    lanes: u32
+    width: f32,
+    end_width: f32,
+    texture: u32,
+    first_lane: u32,
+    lane_count: u32
+}
+
+struct CurveLane {
+    kind: u32,
+    width: f32,
+    carriageway_start: f32,
+    carriageway_width: f32,
+    end_width: f32,
+    end_carriageway_start: f32,
+    end_carriageway_width: f32,
+    dividers: u32
 }
 
 @group(2) @binding(0) var road_texture: texture_2d<f32>;
 @group(2) @binding(1) var road_sampler: sampler;
 @group(2) @binding(2) var<storage> curves: array<Curve>;
+@group(2) @binding(3) var<storage> lanes: array<CurveLane>;
 
-const ROAD_WIDTH: f32 = 1.0;
 const TAU: f32 = 6.28318530718;
 
+// Lane kinds, in the order of `LaneKind`
+const DRIVING: u32 = 0u;
+const BIKE: u32 = 1u;
+const SIDEWALK: u32 = 2u;
+const PARKING: u32 = 3u;
+const MEDIAN: u32 = 4u;
+const SHOULDER: u32 = 5u;
+
+// Double line between lanes going opposite ways, each line this far from the middle
+const DIVIDER_GAP: f32 = 0.02;
+const DIVIDER_LINE: f32 = 0.05;
+const DIVIDER_COLOR: vec4<f32> = vec4(0.9, 0.7, 0.1, 1.0);
+
 fn cross2d(a: vec2<f32>, b: vec2<f32>) -> f32 {
     return a.y * b.x - a.x * b.y;
 }
@@ -36,6 +64,16 @@ fn angle_between(lhs: vec2<f32>, rhs: vec2<f32>) -> f32 {
     return acos(dot(lhs, rhs) / (length(lhs) * length(rhs))) * sign(cross2d(lhs, rhs));
 }
 
+// Tint of each road texture, in the order of `RoadTexture`
+fn texture_tint(texture: u32) -> vec4<f32> {
+    switch texture {
+        case 1u: { return vec4(1.4, 1.35, 1.25, 1.0); }
+        case 2u: { return vec4(0.95, 0.75, 0.6, 1.0); }
+        case 3u: { return vec4(1.2, 1.05, 0.8, 1.0); }
+        default: { return vec4(1.0); }
+    }
+}
+
 fn sd_donut(p: vec2<f32>, ra: f32, th: f32) -> f32 {
     return abs(length(p) - ra) - th;
 }
@@ -44,39 +82,106 @@ fn sd_line(p: vec2<f32>, l: vec2<f32>, th: f32) -> f32 {
     return abs(dot(vec2(-l.y, l.x), p)) - th;
 }
 
+// Color of a lane of `kind`, given the road texture where the lane has its markings
+fn lane_color(kind: u32, texel: vec4<f32>) -> vec4<f32> {
+    if kind == BIKE {
+        return vec4(0.55, 0.22, 0.2, 1.0);
+    }
+    if kind == SIDEWALK {
+        return vec4(0.6, 0.6, 0.58, 1.0);
+    }
+    if kind == PARKING {
+        return vec4(texel.rgb * 0.85, texel.a);
+    }
+    if kind == MEDIAN {
+        return vec4(0.2, 0.4, 0.15, 1.0);
+    }
+    if kind == SHOULDER {
+        return vec4(texel.rgb * 0.8, texel.a);
+    }
+    return texel;
+}
+
 @fragment
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
     var col = vec4(0.05, 0.4, 0.15, 1.0);
 
     var min_distance = 99999.9;
     var min_length = 0.0;
+    var min_along = 0.0;
+    // Distance from the left side of the closest curve
+    var min_across = 0.0;
+    var min_curve = 0u;
     for (var i = u32(0); i < arrayLength(&curves); i++) {
         let pos = (in.world_position.xz - curves[i].center);
-        let thickness = f32(curves[i].lanes) * (ROAD_WIDTH / 2.0);
+        let direction = normalize(curves[i].end);
 
         let length = select(
-            dot(normalize(curves[i].end), pos - curves[i].start),
+            dot(direction, pos - curves[i].start),
             rem_euclid(select(1.0, -1.0, curves[i].twist == 0u) * angle_between(curves[i].start, pos), TAU) * curves[i].radius,
             curves[i].twist != 2u
         );
 
+        // Curves that add or drop lanes change their width evenly along their length
+        let along = clamp(length / curves[i].length, 0.0, 1.0);
+        let thickness = mix(curves[i].width, curves[i].end_width, along) / 2.0;
+
+        // Clockwise curves turn left, around a center on their left
+        let left = select(
+            select(length(pos) - curves[i].radius, curves[i].radius - length(pos), curves[i].twist == 1u),
+            dot(vec2(direction.y, -direction.x), pos),
+            curves[i].twist == 2u
+        );
+
         var distance = select(
             select(
-                sd_line(pos, normalize(curves[i].end), thickness),
+                sd_line(pos, direction, thickness),
                 sd_donut(pos, curves[i].radius, thickness),
                 curves[i].twist != 2u
             ),
-            min(distance(pos, curves[i].start), distance(pos, curves[i].end)) - thickness,
+            min(
+                distance(pos, curves[i].start) - curves[i].width / 2.0,
+                distance(pos, curves[i].end) - curves[i].end_width / 2.0
+            ),
             length < 0.0 || length > curves[i].length
         );
 
 
         min_length = select(min_length, length, min_distance > distance);
+        min_along = select(min_along, along, min_distance > distance);
+        min_across = select(min_across, thickness - left, min_distance > distance);
+        min_curve = select(min_curve, i, min_distance > distance);
         min_distance = min(min_distance, distance);
     }
 
-    let texel = textureSample(road_texture, road_sampler, vec2(-min_distance, fract(min_length)));
-    col = mix(col, texel, step(min_distance, 0.0));
+    // Find the lane of the closest curve the fragment lies in
+    let curve = curves[min_curve];
+    var lane = lanes[curve.first_lane];
+    var lane_width = mix(lane.width, lane.end_width, min_along);
+    var lane_start = 0.0;
+    for (var j = 1u; j < curve.lane_count && min_across > lane_start + lane_width; j++) {
+        lane_start += lane_width;
+        lane = lanes[curve.first_lane + j];
+        lane_width = mix(lane.width, lane.end_width, min_along);
+    }
+
+    // Driving lanes share their markings with the driving lanes next to them going the same way
+    let in_lane = clamp(min_across - lane_start, 0.0, lane_width);
+    let across = in_lane + mix(lane.carriageway_start, lane.end_carriageway_start, min_along);
+    let marking = min(across, mix(lane.carriageway_width, lane.end_carriageway_width, min_along) - across);
+    let u = select(marking, 0.5, lane.kind == SHOULDER);
+
+    let texel = textureSampleLevel(road_texture, road_sampler, vec2(u, fract(min_length)), 0.0) * texture_tint(curve.texture);
+    var lane_col = lane_color(lane.kind, texel);
+
+    let divider = select(
+        select(99999.9, in_lane, (lane.dividers & 1u) != 0u),
+        lane_width - in_lane,
+        (lane.dividers & 2u) != 0u && lane_width - in_lane < in_lane
+    );
+    lane_col = select(lane_col, DIVIDER_COLOR, divider > DIVIDER_GAP && divider < DIVIDER_GAP + DIVIDER_LINE);
+
+    col = mix(col, lane_col, step(min_distance, 0.0));
 
     return col;
-}
\ No newline at end of file
+}
@@ -0,0 +1,207 @@
+//! Runs the traffic simulation without a window and writes traffic metrics.
+//!
+//! ```text
+//! bevy-road-sim <network.ron> <demand.ron> [--seconds N] [--seed N] [--output DIR]
+//! ```
+//!
+//! The network can be any saved network file, RON or JSON, an OpenDRIVE `.xodr` file or an
+//! OpenStreetMap `.osm` extract. Road spawners in it send off vehicles too, on top of the
+//! demand.
+//!
+//! The output directory gets a `summary.json` with all metrics, an `edges.csv` with the
+//! traffic on every edge and a `vehicles.csv` with the number of vehicles on the road every
+//! simulated second.
+
+use std::{
+    error::Error,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use bevy::{ecs::system::CommandQueue, prelude::*};
+use bevy_road::{
+    cli::RunArgs,
+    interop,
+    road::RoadNetworkPlugin,
+    simulation::{
+        clock::{step_simulation, SimulationClock, SimulationRng},
+        demand::DemandFile,
+        metrics::TrafficMetrics,
+        vehicle::Vehicle,
+        SimulationPlugin,
+    },
+};
+use serde::Serialize;
+
+const USAGE: &str =
+    "usage: bevy-road-sim <network.ron> <demand.ron> [--seconds N] [--seed N] [--output DIR]";
+
+struct Args {
+    network: PathBuf,
+    demand: PathBuf,
+    run: RunArgs,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let run = RunArgs::parse(args, USAGE, "metrics")?;
+        let [network, demand] =
+            <[PathBuf; 2]>::try_from(run.paths.clone()).map_err(|_| USAGE.to_string())?;
+
+        Ok(Self {
+            network,
+            demand,
+            run,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct Report {
+    seed: u64,
+    simulated_seconds: f32,
+    departed: usize,
+    arrived: usize,
+    /// Arrived vehicles per hour
+    throughput: f32,
+    mean_travel_time: Option<f32>,
+    vehicles_on_road: usize,
+    edges: Vec<EdgeReport>,
+    vehicle_counts: Vec<VehicleCount>,
+}
+
+/// Traffic on an edge, which is referred to by its index in the network file
+#[derive(Serialize)]
+struct EdgeReport {
+    edge: usize,
+    vehicles: usize,
+    mean_time: Option<f32>,
+    mean_delay: Option<f32>,
+    total_delay: f32,
+}
+
+#[derive(Serialize)]
+struct VehicleCount {
+    time: f32,
+    vehicles: usize,
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse(std::env::args().skip(1))?;
+    let network = interop::load_network(&args.network)?;
+    let demand = DemandFile::load(&args.demand)?;
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins((RoadNetworkPlugin, SimulationPlugin))
+        .insert_resource(SimulationRng::new(args.run.seed))
+        .insert_resource(network.drive_side);
+    app.finish();
+    app.cleanup();
+
+    let mut queue = CommandQueue::default();
+    let entities = network.spawn(&mut Commands::new(&mut queue, &app.world))?;
+    queue.apply(&mut app.world);
+    app.insert_resource(demand.resolve(&entities.edges)?);
+
+    // Lets the road graph pick up the network
+    app.update();
+
+    let timestep = app.world.resource::<SimulationClock>().timestep_seconds();
+    let steps = (args.run.seconds / timestep).ceil() as u64;
+    let steps_per_second = (1.0 / timestep).round().max(1.0) as u64;
+
+    let mut vehicle_counts = Vec::new();
+    for step in 0..steps {
+        step_simulation(&mut app.world);
+
+        if (step + 1) % steps_per_second == 0 {
+            let vehicles = app.world.query::<&Vehicle>().iter(&app.world).count();
+            vehicle_counts.push(VehicleCount {
+                time: (step + 1) as f32 * timestep,
+                vehicles,
+            });
+        }
+    }
+
+    let simulated_seconds = steps as f32 * timestep;
+    let vehicles_on_road = app.world.query::<&Vehicle>().iter(&app.world).count();
+    let metrics = app.world.resource::<TrafficMetrics>();
+
+    let edges = entities
+        .edges
+        .iter()
+        .enumerate()
+        .map(|(index, entity)| {
+            let edge = metrics.edges.get(entity).copied().unwrap_or_default();
+            let mean = |total: f32| (edge.vehicles > 0).then(|| total / edge.vehicles as f32);
+
+            EdgeReport {
+                edge: index,
+                vehicles: edge.vehicles,
+                mean_time: mean(edge.time),
+                mean_delay: mean(edge.delay),
+                total_delay: edge.delay,
+            }
+        })
+        .collect();
+
+    let report = Report {
+        seed: args.run.seed,
+        simulated_seconds,
+        departed: metrics.departed,
+        arrived: metrics.arrived,
+        throughput: metrics.arrived as f32 * 3600.0 / simulated_seconds.max(f32::EPSILON),
+        mean_travel_time: metrics.mean_travel_time(),
+        vehicles_on_road,
+        edges,
+        vehicle_counts,
+    };
+
+    write_report(&args.run.output, &report)
+}
+
+fn write_report(directory: &Path, report: &Report) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(directory)?;
+
+    fs::write(
+        directory.join("summary.json"),
+        serde_json::to_string_pretty(report)?,
+    )?;
+
+    let optional = |value: Option<f32>| value.map_or(String::new(), |value| value.to_string());
+
+    let mut edges = String::from("edge,vehicles,mean_time,mean_delay,total_delay\n");
+    for edge in &report.edges {
+        writeln!(
+            edges,
+            "{},{},{},{},{}",
+            edge.edge,
+            edge.vehicles,
+            optional(edge.mean_time),
+            optional(edge.mean_delay),
+            edge.total_delay
+        )?;
+    }
+    fs::write(directory.join("edges.csv"), edges)?;
+
+    let mut vehicles = String::from("time,vehicles\n");
+    for count in &report.vehicle_counts {
+        writeln!(vehicles, "{},{}", count.time, count.vehicles)?;
+    }
+    fs::write(directory.join("vehicles.csv"), vehicles)?;
+
+    Ok(())
+}
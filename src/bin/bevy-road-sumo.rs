@@ -0,0 +1,81 @@
+//! Exports a saved network and its demand for SUMO, to compare runs of both simulators.
+//!
+//! ```text
+//! bevy-road-sumo <network.ron> [demand.ron] [--seconds N] [--seed N] [--output DIR]
+//! ```
+//!
+//! The output directory gets a `network.net.xml`, a `routes.rou.xml` with the demand and the
+//! vehicles of the road spawners, and a `bevy-road.sumocfg` to run them with the timestep and
+//! seed `bevy-road-sim` uses.
+
+use std::{error::Error, fs, path::PathBuf, process::ExitCode};
+
+use bevy_road::{
+    cli::RunArgs,
+    interop::{self, sumo},
+    simulation::demand::DemandFile,
+};
+
+const USAGE: &str =
+    "usage: bevy-road-sumo <network.ron> [demand.ron] [--seconds N] [--seed N] [--output DIR]";
+const NETWORK_FILE: &str = "network.net.xml";
+const ROUTE_FILE: &str = "routes.rou.xml";
+const CONFIG_FILE: &str = "bevy-road.sumocfg";
+
+struct Args {
+    network: PathBuf,
+    demand: Option<PathBuf>,
+    run: RunArgs,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let run = RunArgs::parse(args, USAGE, "sumo")?;
+        let mut paths = run.paths.iter().cloned();
+        let (Some(network), demand, None) = (paths.next(), paths.next(), paths.next()) else {
+            return Err(USAGE.to_string());
+        };
+
+        Ok(Self {
+            network,
+            demand,
+            run,
+        })
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse(std::env::args().skip(1))?;
+    let network = interop::load_network(&args.network)?;
+    let demand = match &args.demand {
+        Some(path) => DemandFile::load(path)?,
+        None => DemandFile::default(),
+    };
+
+    let directory = &args.run.output;
+    fs::create_dir_all(directory)?;
+    fs::write(
+        directory.join(NETWORK_FILE),
+        sumo::export_network(&network)?,
+    )?;
+    fs::write(
+        directory.join(ROUTE_FILE),
+        sumo::export_routes(&network, &demand, args.run.seconds)?,
+    )?;
+    fs::write(
+        directory.join(CONFIG_FILE),
+        sumo::export_config(NETWORK_FILE, ROUTE_FILE, args.run.seconds, args.run.seed),
+    )?;
+
+    Ok(())
+}
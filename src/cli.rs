@@ -0,0 +1,48 @@
+//! Command line arguments of the headless binaries, which all take files to work on and the
+//! length, seed and output directory of a simulation run.
+
+use std::path::PathBuf;
+
+use crate::simulation::clock::DEFAULT_SEED;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunArgs {
+    /// Arguments that are not options, in order
+    pub paths: Vec<PathBuf>,
+    pub seconds: f32,
+    pub seed: u64,
+    pub output: PathBuf,
+}
+
+impl RunArgs {
+    /// Parses `--seconds N`, `--seed N` and `--output DIR`, with `output` as the default
+    /// output directory. `--help` and malformed options give `usage` as the error.
+    pub fn parse(
+        mut args: impl Iterator<Item = String>,
+        usage: &str,
+        output: &str,
+    ) -> Result<Self, String> {
+        let mut parsed = Self {
+            paths: Vec::new(),
+            seconds: 3600.0,
+            seed: DEFAULT_SEED,
+            output: PathBuf::from(output),
+        };
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{arg} needs a value\n{usage}"));
+
+            match arg.as_str() {
+                "--seconds" => {
+                    parsed.seconds = value()?.parse().map_err(|_| "invalid --seconds")?
+                }
+                "--seed" => parsed.seed = value()?.parse().map_err(|_| "invalid --seed")?,
+                "--output" => parsed.output = PathBuf::from(value()?),
+                "--help" | "-h" => return Err(usage.to_string()),
+                _ => parsed.paths.push(PathBuf::from(arg)),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
@@ -5,9 +5,10 @@ use bevy::prelude::*;
 use bevy::render::primitives::Aabb;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
-use crate::road::edge::RoadEdge;
-use crate::road::placeholder::RoadPlaceholder;
-use crate::road::ROAD_WIDTH;
+use bevy_road::road::cross_section::{LaneDirection, LaneKind};
+use bevy_road::road::edge::RoadEdge;
+use bevy_road::road::placeholder::RoadPlaceholder;
+use bevy_road::simulation::connector::LaneConnectors;
 
 pub struct DebugPlugin;
 impl Plugin for DebugPlugin {
@@ -18,7 +19,8 @@ impl Plugin for DebugPlugin {
             .add_systems(Update, debug_edges)
             .add_systems(Update, draw_axis)
             .add_systems(Update, (debug_aabb, debug_edges_aabb, debug_edges_lanes))
-            .add_systems(Update, debug_road_ends);
+            .add_systems(Update, debug_road_ends)
+            .add_systems(Update, debug_lane_connectors);
     }
 }
 
@@ -76,22 +78,40 @@ fn debug_edges(edges: Query<&RoadEdge, With<RoadPlaceholder>>, mut gizmos: Gizmo
 
 fn debug_edges_lanes(edges: Query<&RoadEdge>, mut gizmos: Gizmos) {
     for edge in &edges {
-        // Draw min
-        let t = edge.get_end_transform(None);
-        let min = (edge.lanes() - 1) as f32 * 0.5 * ROAD_WIDTH;
-        gizmos.ray(t.translation, t.right() * min, Color::RED);
-
-        let base = t.translation + t.right() * (min - -1.0 * ROAD_WIDTH);
-        gizmos.ray(base, *t.forward(), Color::PURPLE);
-
-        let base = t.translation + t.right() * min;
-        gizmos.ray(base, *t.forward(), Color::WHITE);
-
-        let base = t.translation + t.right() * (min - 1.0 * ROAD_WIDTH);
-        gizmos.ray(base, *t.forward(), Color::WHITE);
-
-        let base = t.translation + t.right() * (min - edge.lanes() as f32 * ROAD_WIDTH);
-        gizmos.ray(base, *t.forward(), Color::BLUE);
+        let t = edge.end();
+        let cross_section = &edge.road_type().cross_section;
+
+        // Strips of the cross-section at the end of the edge, colored by kind
+        let mut left = cross_section.width_at(1.0) * 0.5;
+        for lane in &cross_section.lanes {
+            let color = match lane.kind {
+                LaneKind::Driving if lane.direction == LaneDirection::Backward => Color::ORANGE,
+                LaneKind::Driving => Color::WHITE,
+                LaneKind::Bike => Color::RED,
+                LaneKind::Sidewalk => Color::GRAY,
+                LaneKind::Parking => Color::BLUE,
+                LaneKind::Median => Color::GREEN,
+                LaneKind::Shoulder => Color::YELLOW,
+            };
+
+            let width = lane.width_at(1.0);
+            let base = t.translation + *t.left() * left;
+            gizmos.line(base, base + *t.right() * width, color);
+            left -= width;
+        }
+
+        // Middle of the driving lanes, and where roads join from the side
+        for (offset, _) in cross_section.driving(1.0) {
+            gizmos.ray(
+                t.translation + *t.left() * offset,
+                *t.forward(),
+                Color::WHITE,
+            );
+        }
+        for lane in [-1.0, edge.lanes() as f32] {
+            let base = t.translation + *t.left() * cross_section.offset(lane, 1.0);
+            gizmos.ray(base, *t.forward(), Color::PURPLE);
+        }
     }
 }
 
@@ -128,3 +148,17 @@ fn draw_axis(mut gizmos: Gizmos<DebugGizmos>) {
     gizmos.ray(Vec3::ZERO, Vec3::Y, Color::GREEN);
     gizmos.ray(Vec3::ZERO, Vec3::X, Color::RED);
 }
+
+/// Paths vehicles take through junctions
+fn debug_lane_connectors(connectors: Res<LaneConnectors>, mut gizmos: Gizmos<DebugGizmos>) {
+    for connector in connectors.iter() {
+        let steps = (connector.length() / 0.1).ceil().max(1.0) as u32;
+        gizmos.linestrip(
+            (0..=steps).map(|step| {
+                let length = connector.length() * step as f32 / steps as f32;
+                connector.interpolate(length).translation
+            }),
+            Color::CYAN,
+        );
+    }
+}
@@ -0,0 +1,46 @@
+//! Exchange of road networks with other simulators and map data
+
+use std::{fs, path::Path};
+
+use crate::road::network::{NetworkError, NetworkFormat, RoadNetwork};
+
+pub mod opendrive;
+pub mod osm;
+pub mod sumo;
+
+/// Loads a network file, importing it first if it is not a native `.ron` or `.json` network.
+/// The extension picks the format.
+pub fn load_network(path: &Path) -> Result<RoadNetwork, NetworkError> {
+    read_network(&read(path)?, path)
+}
+
+/// Reads the contents of the network file at `path`, in the format its extension picks
+pub fn read_network(text: &str, path: &Path) -> Result<RoadNetwork, NetworkError> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("xodr") => opendrive::import(text),
+        Some("osm") => osm::import(text),
+        _ => {
+            let format = NetworkFormat::from_path(path)
+                .ok_or_else(|| NetworkError::UnknownFormat(path.to_path_buf()))?;
+            RoadNetwork::read(text, format)
+        }
+    }
+}
+
+/// Saves a network file, exporting it if the extension asks for another format
+pub fn save_network(network: &RoadNetwork, path: &Path) -> Result<(), NetworkError> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("xodr") => write(path, &opendrive::export(network)?),
+        _ => network.save(path),
+    }
+}
+
+fn read(path: &Path) -> Result<String, NetworkError> {
+    fs::read_to_string(path)
+        .map_err(|error| NetworkError::Io(format!("{}: {error}", path.display())))
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), NetworkError> {
+    fs::write(path, contents)
+        .map_err(|error| NetworkError::Io(format!("{}: {error}", path.display())))
+}
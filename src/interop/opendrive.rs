@@ -0,0 +1,660 @@
+//! OpenDRIVE (`.xodr`) export and import.
+//!
+//! Every edge becomes a road with a single `<line>` or `<arc>` geometry along its centerline.
+//! With right-hand traffic, the lanes of an edge's cross-section right of its backward lanes
+//! are right lanes, which drive along the road, and a lane offset moves them back in place.
+//! The lanes further left belong to the edge going the other way, which writes them as its
+//! own road. Left-hand traffic is the mirror image, with left lanes and the `LHT` rule.
+//! OpenDRIVE `x` and `y` are world `x` and `-z`, so headings count counterclockwise from +X as
+//! seen from above. Speed limits are in world units per second, written as meters per second.
+
+use std::{
+    fmt::{self, Write},
+    str::FromStr,
+};
+
+use bevy::prelude::*;
+use roxmltree::{Document, Node};
+
+use crate::road::{
+    biarc::compute_biarc,
+    cross_section::{CrossSection, DriveSide, LaneKind, LaneProfile},
+    edge::{RoadEdge, Twist},
+    network::{NetworkBuilder, NetworkError, RoadNetwork, NETWORK_VERSION},
+    palette::{RoadType, DEFAULT_SPEED_LIMIT},
+};
+
+/// Steps taken to trace a spiral to its end
+const SPIRAL_STEPS: u32 = 32;
+
+/// Writes the network as an OpenDRIVE file. Roads link to each other where the road goes on
+/// without branching. Spawners have no place in OpenDRIVE and are left out.
+pub fn export(network: &RoadNetwork) -> Result<String, NetworkError> {
+    network.validate()?;
+
+    let mut xodr = String::new();
+    write_network(&mut xodr, network).expect("writing to a String can't fail");
+    Ok(xodr)
+}
+
+fn write_network(xodr: &mut String, network: &RoadNetwork) -> fmt::Result {
+    let mut incoming = vec![Vec::new(); network.nodes.len()];
+    let mut outgoing = vec![Vec::new(); network.nodes.len()];
+    for (index, edge) in network.edges.iter().enumerate() {
+        outgoing[edge.start_node].push(index);
+        incoming[edge.end_node].push(index);
+    }
+
+    let through = |node: usize| match (incoming[node].as_slice(), outgoing[node].as_slice()) {
+        (&[from], &[to]) => Some((from, to)),
+        _ => None,
+    };
+
+    writeln!(xodr, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(xodr, "<OpenDRIVE>")?;
+    writeln!(
+        xodr,
+        r#"  <header revMajor="1" revMinor="6" name="bevy-road" version="{NETWORK_VERSION}"/>"#
+    )?;
+
+    for (id, edge) in network.edges.iter().enumerate() {
+        let predecessor = through(edge.start_node).map(|(from, _)| from);
+        let successor = through(edge.end_node).map(|(_, to)| to);
+        write_road(xodr, network, id, predecessor, successor)?;
+    }
+
+    writeln!(xodr, "</OpenDRIVE>")
+}
+
+fn write_road(
+    xodr: &mut String,
+    network: &RoadNetwork,
+    id: usize,
+    predecessor: Option<usize>,
+    successor: Option<usize>,
+) -> fmt::Result {
+    let edge = &network.edges[id];
+    let start = edge.start.translation;
+    let forward = edge.start.forward();
+    let heading = (-forward.z).atan2(forward.x);
+
+    let side = network.drive_side;
+    let rule = match side {
+        DriveSide::Right => "RHT",
+        DriveSide::Left => "LHT",
+    };
+    writeln!(
+        xodr,
+        r#"  <road name="Road Edge {id}" length="{}" id="{id}" junction="-1" rule="{rule}">"#,
+        edge.length
+    )?;
+
+    if predecessor.is_some() || successor.is_some() {
+        writeln!(xodr, "    <link>")?;
+        if let Some(predecessor) = predecessor {
+            writeln!(
+                xodr,
+                r#"      <predecessor elementType="road" elementId="{predecessor}" contactPoint="end"/>"#
+            )?;
+        }
+        if let Some(successor) = successor {
+            writeln!(
+                xodr,
+                r#"      <successor elementType="road" elementId="{successor}" contactPoint="start"/>"#
+            )?;
+        }
+        writeln!(xodr, "    </link>")?;
+    }
+
+    writeln!(xodr, r#"    <type s="0" type="town">"#)?;
+    writeln!(
+        xodr,
+        r#"      <speed max="{}" unit="m/s"/>"#,
+        edge.road_type.speed_limit
+    )?;
+    writeln!(xodr, "    </type>")?;
+
+    writeln!(xodr, "    <planView>")?;
+    writeln!(
+        xodr,
+        r#"      <geometry s="0" x="{}" y="{}" hdg="{heading}" length="{}">"#,
+        start.x, -start.z, edge.length
+    )?;
+    match edge.twist {
+        Twist::Straight => writeln!(xodr, "        <line/>")?,
+        Twist::Clockwise => writeln!(xodr, r#"        <arc curvature="{}"/>"#, 1.0 / edge.radius)?,
+        Twist::CounterClockwise => {
+            writeln!(xodr, r#"        <arc curvature="{}"/>"#, -1.0 / edge.radius)?
+        }
+    }
+    writeln!(xodr, "      </geometry>")?;
+    writeln!(xodr, "    </planView>")?;
+
+    // Lanes that are added or dropped along the edge change their width linearly, which moves
+    // the reference line too
+    let cross_section = &edge.road_type.cross_section;
+    let (left, _) = split_lanes(cross_section, side);
+    let offset = |along: f32| {
+        cross_section.width_at(along) * 0.5
+            - left.iter().map(|lane| lane.width_at(along)).sum::<f32>()
+    };
+    let (element, sign) = match side {
+        DriveSide::Right => ("right", "-"),
+        DriveSide::Left => ("left", ""),
+    };
+
+    writeln!(xodr, "    <lanes>")?;
+    writeln!(
+        xodr,
+        r#"      <laneOffset s="0" a="{}" b="{}" c="0" d="0"/>"#,
+        offset(0.0),
+        (offset(1.0) - offset(0.0)) / edge.length
+    )?;
+    writeln!(xodr, r#"      <laneSection s="0">"#)?;
+    writeln!(xodr, "        <center>")?;
+    writeln!(
+        xodr,
+        r#"          <lane id="0" type="none" level="false"/>"#
+    )?;
+    writeln!(xodr, "        </center>")?;
+    writeln!(xodr, "        <{element}>")?;
+    for (lane, profile) in (1..).zip(own_lanes(&edge.road_type.cross_section, side)) {
+        writeln!(
+            xodr,
+            r#"          <lane id="{sign}{lane}" type="{}" level="false">"#,
+            lane_type(profile.kind)
+        )?;
+
+        // Lanes carry on into the lane with the same number, if there is one
+        let continues = |road: &usize| {
+            lane <= own_lanes(&network.edges[*road].road_type.cross_section, side).len()
+        };
+        let predecessor = predecessor.filter(continues);
+        let successor = successor.filter(continues);
+        if predecessor.is_some() || successor.is_some() {
+            writeln!(xodr, "            <link>")?;
+            if predecessor.is_some() {
+                writeln!(xodr, r#"              <predecessor id="{sign}{lane}"/>"#)?;
+            }
+            if successor.is_some() {
+                writeln!(xodr, r#"              <successor id="{sign}{lane}"/>"#)?;
+            }
+            writeln!(xodr, "            </link>")?;
+        }
+
+        writeln!(
+            xodr,
+            r#"            <width sOffset="0" a="{}" b="{}" c="0" d="0"/>"#,
+            profile.width,
+            (profile.width_at(1.0) - profile.width) / edge.length
+        )?;
+        writeln!(xodr, "          </lane>")?;
+    }
+    writeln!(xodr, "        </{element}>")?;
+    writeln!(xodr, "      </laneSection>")?;
+    writeln!(xodr, "    </lanes>")?;
+    writeln!(xodr, "  </road>")
+}
+
+/// Lanes left and right of the reference line, which runs between the backward lanes and the
+/// lanes on the side traffic keeps to
+fn split_lanes(cross_section: &CrossSection, side: DriveSide) -> (&[LaneProfile], &[LaneProfile]) {
+    let lanes = &cross_section.lanes;
+    let reference = match side {
+        DriveSide::Right => lanes
+            .iter()
+            .rposition(LaneProfile::is_opposing)
+            .map_or(0, |index| index + 1),
+        DriveSide::Left => lanes
+            .iter()
+            .position(LaneProfile::is_opposing)
+            .unwrap_or(lanes.len()),
+    };
+
+    lanes.split_at(reference)
+}
+
+/// Lanes of the road itself, from the reference line outwards
+fn own_lanes(cross_section: &CrossSection, side: DriveSide) -> Vec<LaneProfile> {
+    match split_lanes(cross_section, side) {
+        (_, right) if side == DriveSide::Right => right.to_vec(),
+        (left, _) => left.iter().rev().copied().collect(),
+    }
+}
+
+/// OpenDRIVE lane type of lanes of `kind`
+fn lane_type(kind: LaneKind) -> &'static str {
+    match kind {
+        LaneKind::Driving => "driving",
+        LaneKind::Bike => "biking",
+        LaneKind::Sidewalk => "sidewalk",
+        LaneKind::Parking => "parking",
+        LaneKind::Median => "median",
+        LaneKind::Shoulder => "shoulder",
+    }
+}
+
+/// Kind of lanes of OpenDRIVE lane type `lane_type`. Types without a kind of their own, such
+/// as borders and curbs, become shoulders.
+fn lane_kind(lane_type: &str) -> LaneKind {
+    match lane_type {
+        "driving" => LaneKind::Driving,
+        "biking" => LaneKind::Bike,
+        "sidewalk" | "walking" => LaneKind::Sidewalk,
+        "parking" => LaneKind::Parking,
+        "median" => LaneKind::Median,
+        _ => LaneKind::Shoulder,
+    }
+}
+
+/// Reads the roads of an OpenDRIVE file into a network. Every geometry of a road becomes an
+/// edge for its lanes on the side traffic keeps to and one in the other direction for the
+/// other side, and edges connect wherever their ends meet. The lanes of the first lane section
+/// make up the cross-sections, and sides without driving lanes are left out. Lane widths and
+/// lane offsets that change along a road taper linearly between their values at either end
+/// of each geometry. Traffic keeps to the left when any road has the `LHT` rule. Spirals are
+/// fitted with a pair of arcs and polynomial geometries are not supported.
+pub fn import(text: &str) -> Result<RoadNetwork, NetworkError> {
+    let document =
+        Document::parse(text).map_err(|error| NetworkError::Format(error.to_string()))?;
+    let root = document.root_element();
+    if !root.has_tag_name("OpenDRIVE") {
+        return Err(NetworkError::Format("not an OpenDRIVE file".to_string()));
+    }
+
+    let left = children(root, "road").any(|road| road.attribute("rule") == Some("LHT"));
+    let mut network = NetworkBuilder::new(RoadNetwork {
+        drive_side: match left {
+            true => DriveSide::Left,
+            false => DriveSide::Right,
+        },
+        ..default()
+    });
+
+    for road in children(root, "road") {
+        let id = road.attribute("id").unwrap_or_default();
+        let road_error = |error: String| NetworkError::Format(format!("road {id}: {error}"));
+
+        let length = number::<f32>(road, "length").map_err(road_error)?;
+        let groups = lane_groups(road, length, network.network.drive_side).map_err(road_error)?;
+        let plan_view =
+            child(road, "planView").ok_or_else(|| road_error("no <planView>".into()))?;
+
+        for geometry in children(plan_view, "geometry") {
+            let Some(geometry) = Geometry::read(geometry).map_err(road_error)? else {
+                continue;
+            };
+
+            for group in &groups {
+                for edge in geometry.edges(group, length) {
+                    network.push_edge(&edge);
+                }
+            }
+        }
+    }
+
+    Ok(network.network)
+}
+
+/// The lanes on one side of a road
+struct LaneGroup {
+    /// Distance of the middle of the lanes to the left of the reference line, at the start and
+    /// end of the road
+    offset: f32,
+    end_offset: f32,
+    /// Road type along the whole road, in the direction of the road
+    road_type: RoadType,
+    /// Whether the lanes drive against the direction of the road
+    reverse: bool,
+}
+
+fn lane_groups(road: Node, length: f32, drive_side: DriveSide) -> Result<Vec<LaneGroup>, String> {
+    let lanes = child(road, "lanes").ok_or("no <lanes>")?;
+    let (lane_offset, end_lane_offset) = match child(lanes, "laneOffset") {
+        Some(lane_offset) => (cubic(lane_offset, 0.0)?, cubic(lane_offset, length)?),
+        None => (0.0, 0.0),
+    };
+    let section = child(lanes, "laneSection").ok_or("no <laneSection>")?;
+
+    let mut groups = Vec::new();
+    for (side, direction) in [("right", -1.0), ("left", 1.0)] {
+        let Some(side) = child(section, side) else {
+            continue;
+        };
+
+        let mut side_lanes = children(side, "lane")
+            .map(|lane| Ok((number::<i32>(lane, "id")?.abs(), lane)))
+            .collect::<Result<Vec<(i32, Node)>, String>>()?;
+        side_lanes.sort_by_key(|(id, _)| *id);
+
+        // From the reference line outwards, which is left to right along the lanes with
+        // right-hand traffic and right to left with left-hand traffic
+        let mut lanes = Vec::new();
+        for (_, lane) in side_lanes {
+            let (width, end_width) = match child(lane, "width") {
+                Some(width) => (cubic(width, 0.0)?, cubic(width, length)?.max(0.0)),
+                None => (0.0, 0.0),
+            };
+
+            if width > 0.0 || end_width > 0.0 {
+                let kind = lane_kind(lane.attribute("type").unwrap_or_default());
+                lanes.push(LaneProfile::new(kind, width.max(0.0)).tapered(end_width));
+            }
+        }
+
+        if drive_side == DriveSide::Left {
+            lanes.reverse();
+        }
+
+        let cross_section = CrossSection { lanes };
+        if cross_section.driving_lanes() == 0 {
+            continue;
+        }
+
+        groups.push(LaneGroup {
+            offset: lane_offset + direction * cross_section.width_at(0.0) * 0.5,
+            end_offset: end_lane_offset + direction * cross_section.width_at(1.0) * 0.5,
+            road_type: RoadType {
+                cross_section,
+                speed_limit: speed_limit(road)?.unwrap_or(DEFAULT_SPEED_LIMIT),
+                ..default()
+            },
+            reverse: match drive_side {
+                DriveSide::Right => direction > 0.0,
+                DriveSide::Left => direction < 0.0,
+            },
+        });
+    }
+
+    Ok(groups)
+}
+
+/// Speed limit of the first road type, in meters per second
+fn speed_limit(road: Node) -> Result<Option<f32>, String> {
+    let Some(speed) = child(road, "type").and_then(|road_type| child(road_type, "speed")) else {
+        return Ok(None);
+    };
+
+    let max = number::<f32>(speed, "max")?;
+    let factor = match speed.attribute("unit").unwrap_or("m/s") {
+        "m/s" => 1.0,
+        "km/h" => 1.0 / 3.6,
+        "mph" => 0.44704,
+        unit => return Err(format!("unknown speed unit {unit}")),
+    };
+
+    if !(max.is_finite() && max > 0.0) {
+        return Err(format!("speed limit {max} is not positive"));
+    }
+
+    Ok(Some(max * factor))
+}
+
+/// Piece of the reference line of a road
+struct Geometry {
+    /// Where the piece starts along the road, and its length
+    s: f32,
+    length: f32,
+    start: Transform,
+    end: Transform,
+    /// Whether the piece is a line
+    straight: bool,
+    /// Whether the piece is neither a line nor an arc and has to be fitted
+    fitted: bool,
+}
+
+impl Geometry {
+    /// Reads a `<geometry>`, or nothing if it has no length
+    fn read(geometry: Node) -> Result<Option<Self>, String> {
+        let s: f32 = number(geometry, "s")?;
+        let x: f32 = number(geometry, "x")?;
+        let y: f32 = number(geometry, "y")?;
+        let heading: f32 = number(geometry, "hdg")?;
+        let length: f32 = number(geometry, "length")?;
+        if length <= f32::EPSILON {
+            return Ok(None);
+        }
+
+        let line = |x: f32, y: f32| {
+            let end = pose(
+                x + heading.cos() * length,
+                y + heading.sin() * length,
+                heading,
+            );
+            (end, true, false)
+        };
+
+        let shape = geometry
+            .children()
+            .find(|node| node.is_element())
+            .ok_or("empty <geometry>")?;
+        let (end, straight, fitted) = match shape.tag_name().name() {
+            "line" => line(x, y),
+            "arc" => {
+                let curvature: f32 = number(shape, "curvature")?;
+                if curvature.abs() <= f32::EPSILON {
+                    line(x, y)
+                } else {
+                    let end_heading = heading + curvature * length;
+                    let end = pose(
+                        x + (end_heading.sin() - heading.sin()) / curvature,
+                        y - (end_heading.cos() - heading.cos()) / curvature,
+                        end_heading,
+                    );
+                    (end, false, false)
+                }
+            }
+            "spiral" => {
+                let start_curvature: f32 = number(shape, "curvStart")?;
+                let end_curvature: f32 = number(shape, "curvEnd")?;
+                let heading_at = |s: f32| {
+                    heading
+                        + start_curvature * s
+                        + (end_curvature - start_curvature) * s * s / (2.0 * length)
+                };
+
+                let step = length / SPIRAL_STEPS as f32;
+                let (mut end_x, mut end_y) = (x, y);
+                for index in 0..SPIRAL_STEPS {
+                    let heading = heading_at((index as f32 + 0.5) * step);
+                    end_x += heading.cos() * step;
+                    end_y += heading.sin() * step;
+                }
+
+                (pose(end_x, end_y, heading_at(length)), false, true)
+            }
+            other => return Err(format!("unsupported <{other}> geometry")),
+        };
+
+        Ok(Some(Self {
+            s,
+            length,
+            start: pose(x, y, heading),
+            end,
+            straight,
+            fitted,
+        }))
+    }
+
+    /// Edges running along the piece where the lanes of `group` are, on a road of `length`
+    fn edges(&self, group: &LaneGroup, length: f32) -> Vec<RoadEdge> {
+        let (from, to) = (self.s / length, (self.s + self.length) / length);
+        let offset = |along: f32| group.offset + (group.end_offset - group.offset) * along;
+        let shift = |pose: Transform, offset: f32| {
+            pose.with_translation(pose.translation + *pose.left() * offset)
+        };
+        let (start, end) = (shift(self.start, offset(from)), shift(self.end, offset(to)));
+        let (mut start, end, from, to) = match group.reverse {
+            false => (start, end, from, to),
+            true => (reverse(end), reverse(start), to, from),
+        };
+        let road_type = RoadType {
+            cross_section: group.road_type.cross_section.between(from, to),
+            ..group.road_type.clone()
+        };
+
+        // Lanes moving sideways along a line still run straight
+        if self.straight {
+            start.look_at(end.translation, Vec3::Y);
+        }
+
+        match self.fitted {
+            false => vec![RoadEdge::from_start_end(start, end.translation, road_type)],
+            true => {
+                let (first, second) = compute_biarc(start, end, road_type);
+                vec![first, second]
+            }
+        }
+    }
+}
+
+/// Transform at an OpenDRIVE position, facing along `heading`
+fn pose(x: f32, y: f32, heading: f32) -> Transform {
+    Transform::from_xyz(x, 0.0, -y)
+        .looking_to(Vec3::new(heading.cos(), 0.0, -heading.sin()), Vec3::Y)
+}
+
+fn reverse(pose: Transform) -> Transform {
+    pose.looking_to(-*pose.forward(), Vec3::Y)
+}
+
+/// Value of the cubic polynomial of a `<width>` or `<laneOffset>` at `s` along the road, from
+/// where the record starts
+fn cubic(node: Node, s: f32) -> Result<f32, String> {
+    // Higher coefficients may be left out when they are zero
+    let coefficient = |attribute| match node.has_attribute(attribute) {
+        true => number::<f32>(node, attribute),
+        false => Ok(0.0),
+    };
+    let start = coefficient(match node.has_attribute("sOffset") {
+        true => "sOffset",
+        false => "s",
+    })?;
+    let ds = s - start;
+    let (a, b, c, d) = (
+        number::<f32>(node, "a")?,
+        coefficient("b")?,
+        coefficient("c")?,
+        coefficient("d")?,
+    );
+
+    Ok(a + ds * (b + ds * (c + ds * d)))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children()
+        .filter(move |child| child.has_tag_name(name))
+}
+
+fn number<T: FromStr>(node: Node, attribute: &str) -> Result<T, String> {
+    node.attribute(attribute)
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| format!("<{}> has no valid {attribute}", node.tag_name().name()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A line east and an arc turning north from its end, with two driving lanes and a
+    /// sidewalk that narrows along the arc
+    fn network() -> RoadNetwork {
+        let cross_section = CrossSection {
+            lanes: vec![
+                LaneProfile::new(LaneKind::Driving, 1.0),
+                LaneProfile::new(LaneKind::Driving, 1.0),
+                LaneProfile::new(LaneKind::Sidewalk, 0.5),
+            ],
+        };
+        let road_type = RoadType {
+            cross_section,
+            speed_limit: 8.0,
+            ..default()
+        };
+
+        let start = Transform::IDENTITY.looking_to(Vec3::X, Vec3::Y);
+        let line = RoadEdge::from_start_end(start, Vec3::new(20.0, 0.0, 0.0), road_type.clone());
+
+        let mut tapered = road_type;
+        tapered.cross_section.lanes[2] = tapered.cross_section.lanes[2].tapered(0.25);
+        let arc = RoadEdge::from_start_end(line.end(), Vec3::new(30.0, 0.0, -10.0), tapered);
+
+        let mut builder = NetworkBuilder::new(RoadNetwork::default());
+        builder.push_edge(&line);
+        builder.push_edge(&arc);
+        builder.network
+    }
+
+    fn assert_close(a: f32, b: f32, what: &str) {
+        assert!((a - b).abs() < 1e-3, "{what}: {a} != {b}");
+    }
+
+    #[test]
+    fn export_and_import_round_trip() {
+        let network = network();
+        let xodr = export(&network).unwrap();
+        assert!(xodr.contains("<line/>") && xodr.contains("<arc curvature="));
+
+        let imported = import(&xodr).unwrap();
+        assert_eq!(imported.drive_side, DriveSide::Right);
+        assert_eq!(imported.nodes.len(), network.nodes.len());
+        assert_eq!(imported.edges.len(), network.edges.len());
+
+        for (imported, edge) in imported.edges.iter().zip(&network.edges) {
+            assert_eq!(
+                (imported.start_node, imported.end_node),
+                (edge.start_node, edge.end_node)
+            );
+            assert_eq!(imported.twist, edge.twist);
+            assert_close(imported.length, edge.length, "length");
+            assert_close(imported.radius, edge.radius, "radius");
+            for (a, b) in [(imported.start, edge.start), (imported.end, edge.end)] {
+                assert!(
+                    a.translation.distance(b.translation) < 1e-3,
+                    "{a:?} != {b:?}"
+                );
+                assert!(a.forward().dot(*b.forward()) > 1.0 - 1e-4, "{a:?} != {b:?}");
+            }
+
+            let road_type = &imported.road_type;
+            assert_close(
+                road_type.speed_limit,
+                edge.road_type.speed_limit,
+                "speed limit",
+            );
+            let lanes = &road_type.cross_section.lanes;
+            assert_eq!(lanes.len(), edge.road_type.cross_section.lanes.len());
+            for (lane, original) in lanes.iter().zip(&edge.road_type.cross_section.lanes) {
+                assert_eq!(lane.kind, original.kind);
+                assert_eq!(lane.direction, original.direction);
+                assert_close(lane.width_at(0.0), original.width_at(0.0), "start width");
+                assert_close(lane.width_at(1.0), original.width_at(1.0), "end width");
+            }
+        }
+    }
+
+    #[test]
+    fn speed_limits_convert_to_meters_per_second() {
+        let xodr = export(&network()).unwrap();
+
+        for (unit, factor) in [("km/h", 1.0 / 3.6), ("mph", 0.44704)] {
+            let text = xodr.replace(r#"unit="m/s""#, &format!(r#"unit="{unit}""#));
+            let imported = import(&text).unwrap();
+            for edge in &imported.edges {
+                assert_close(edge.road_type.speed_limit, 8.0 * factor, unit);
+            }
+        }
+
+        let text = xodr.replace(r#"unit="m/s""#, r#"unit="knots""#);
+        assert!(import(&text).is_err());
+        let text = xodr.replace(r#"max="8""#, r#"max="0""#);
+        assert!(import(&text).is_err());
+    }
+}
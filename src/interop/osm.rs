@@ -0,0 +1,335 @@
+//! Import of OpenStreetMap (`.osm`) XML extracts.
+//!
+//! Ways tagged as roads for cars become edges. Ways are cut where they share a node with
+//! another way, so edges of different ways connect there. Each piece is fitted with a pair of
+//! arcs between consecutive way nodes, or a line where the way runs straight.
+//!
+//! Traffic keeps to the left when any element of the extract, usually the country it lies in,
+//! is tagged `driving_side=left`.
+
+use bevy::{prelude::*, utils::HashMap};
+use roxmltree::{Document, Node};
+
+use crate::road::{
+    biarc::compute_biarc,
+    cross_section::{CrossSection, DriveSide, LaneKind, LaneProfile},
+    edge::RoadEdge,
+    network::{NetworkBuilder, NetworkError, RoadNetwork},
+    palette::RoadType,
+    ROAD_WIDTH,
+};
+
+/// Mean earth radius in meters
+const EARTH_RADIUS: f64 = 6_371_000.0;
+/// Width of a real lane, which is drawn `ROAD_WIDTH` wide
+const LANE_WIDTH_METERS: f64 = 3.5;
+/// Values of the `highway` tag of roads cars drive on
+const HIGHWAYS: [&str; 17] = [
+    "motorway",
+    "motorway_link",
+    "trunk",
+    "trunk_link",
+    "primary",
+    "primary_link",
+    "secondary",
+    "secondary_link",
+    "tertiary",
+    "tertiary_link",
+    "unclassified",
+    "residential",
+    "living_street",
+    "service",
+    "road",
+    "busway",
+    "track",
+];
+
+/// Reads the roads of an OpenStreetMap extract into a network. Positions are projected onto
+/// the XZ plane around the middle of the extract, with north along -Z, and scaled so real
+/// lanes are `ROAD_WIDTH` wide. Two-way roads become an edge in each direction, with at least
+/// one lane each, so a two-way road with `lanes=1` gets a lane each way.
+pub fn import(text: &str) -> Result<RoadNetwork, NetworkError> {
+    let document =
+        Document::parse(text).map_err(|error| NetworkError::Format(error.to_string()))?;
+    let root = document.root_element();
+    if !root.has_tag_name("osm") {
+        return Err(NetworkError::Format(
+            "not an OpenStreetMap file".to_string(),
+        ));
+    }
+
+    let coordinates = root
+        .children()
+        .filter(|node| node.has_tag_name("node"))
+        .map(|node| {
+            let id = node.attribute("id").unwrap_or_default();
+            let coordinate = |name: &str| {
+                let error = || NetworkError::Format(format!("node {id} has no valid {name}"));
+                node.attribute(name)
+                    .and_then(|value| value.parse::<f64>().ok())
+                    .ok_or_else(error)
+            };
+
+            Ok((id, (coordinate("lat")?, coordinate("lon")?)))
+        })
+        .collect::<Result<HashMap<&str, (f64, f64)>, NetworkError>>()?;
+
+    let ways = root
+        .children()
+        .filter(|node| node.has_tag_name("way"))
+        .filter_map(Way::read)
+        .collect::<Vec<Way>>();
+
+    // Nodes used more than once, by different ways or twice by the same one, are junctions
+    let mut uses = HashMap::<&str, usize>::new();
+    for way in &ways {
+        for &node in &way.nodes {
+            *uses.entry(node).or_default() += 1;
+        }
+    }
+
+    let projection = Projection::new(root, ways.iter().flat_map(|way| &way.nodes), &coordinates);
+
+    let left = root.descendants().any(|node| {
+        node.has_tag_name("tag")
+            && node.attribute("k") == Some("driving_side")
+            && node.attribute("v") == Some("left")
+    });
+    let mut network = NetworkBuilder::new(RoadNetwork {
+        drive_side: match left {
+            true => DriveSide::Left,
+            false => DriveSide::Right,
+        },
+        ..default()
+    });
+    for way in &ways {
+        let mut piece = Vec::new();
+
+        for (index, &node) in way.nodes.iter().enumerate() {
+            let Some(&coordinate) = coordinates.get(node) else {
+                return Err(NetworkError::Format(format!(
+                    "way {} refers to missing node {node}",
+                    way.id
+                )));
+            };
+
+            let position = projection.project(coordinate);
+            if !piece
+                .last()
+                .is_some_and(|last: &Vec3| last.distance(position) <= f32::EPSILON)
+            {
+                piece.push(position);
+            }
+
+            let end = index + 1 == way.nodes.len();
+            if uses[node] > 1 || end {
+                way.push_edges(&mut network, &piece);
+                piece = vec![position];
+            }
+        }
+    }
+
+    Ok(network.network)
+}
+
+/// Road from OpenStreetMap, with the lanes driving along and against it
+struct Way<'a> {
+    id: &'a str,
+    nodes: Vec<&'a str>,
+    forward_lanes: u8,
+    backward_lanes: u8,
+}
+
+impl<'a> Way<'a> {
+    /// Reads a `<way>`, or nothing if it isn't a road for cars
+    fn read(way: Node<'a, '_>) -> Option<Self> {
+        let tags = way
+            .children()
+            .filter(|node| node.has_tag_name("tag"))
+            .filter_map(|tag| Some((tag.attribute("k")?, tag.attribute("v")?)))
+            .collect::<HashMap<&str, &str>>();
+
+        let highway = *tags.get("highway")?;
+        if !HIGHWAYS.contains(&highway) {
+            return None;
+        }
+
+        let count = |key: &str| {
+            tags.get(key)?
+                .split(';')
+                .next()?
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|&lanes| lanes > 0)
+        };
+        let lanes = count("lanes");
+
+        let oneway = match tags.get("oneway").copied() {
+            Some("yes" | "true" | "1") => Some(true),
+            Some("-1" | "reverse") => Some(false),
+            Some("no") => None,
+            _ => {
+                let junction = tags.get("junction").copied();
+                (highway == "motorway" || matches!(junction, Some("roundabout" | "circular")))
+                    .then_some(true)
+            }
+        };
+
+        let (forward_lanes, backward_lanes) = match oneway {
+            Some(true) => (lanes.unwrap_or(1), 0),
+            Some(false) => (0, lanes.unwrap_or(1)),
+            None => match (count("lanes:forward"), count("lanes:backward")) {
+                (Some(forward), Some(backward)) => (forward, backward),
+                (Some(forward), None) => (
+                    forward,
+                    lanes.map_or(1, |lanes| lanes.saturating_sub(forward).max(1)),
+                ),
+                (None, Some(backward)) => (
+                    lanes.map_or(1, |lanes| lanes.saturating_sub(backward).max(1)),
+                    backward,
+                ),
+                // A single lane shared by both directions has no equivalent in the network, so
+                // it becomes a lane each way
+                (None, None) => {
+                    let lanes = lanes.unwrap_or(2);
+                    (lanes.div_ceil(2), (lanes / 2).max(1))
+                }
+            },
+        };
+
+        Some(Self {
+            id: way.attribute("id").unwrap_or_default(),
+            nodes: way
+                .children()
+                .filter(|node| node.has_tag_name("nd"))
+                .filter_map(|node| node.attribute("ref"))
+                .collect(),
+            forward_lanes,
+            backward_lanes,
+        })
+    }
+
+    /// Adds the edges in both directions along a piece of the way
+    fn push_edges(&self, network: &mut NetworkBuilder, points: &[Vec3]) {
+        if points.len() < 2 {
+            return;
+        }
+
+        // Lanes against the way lie on the side of the road traffic doesn't keep to
+        let lane = LaneProfile::new(LaneKind::Driving, ROAD_WIDTH);
+        let road_type = RoadType {
+            cross_section: CrossSection {
+                lanes: [
+                    vec![lane.backward(); self.backward_lanes as usize],
+                    vec![lane; self.forward_lanes as usize],
+                ]
+                .concat(),
+            },
+            ..default()
+        }
+        .for_side(network.network.drive_side);
+
+        if self.forward_lanes > 0 {
+            for edge in fit_polyline(points, &road_type) {
+                network.push_edge(&edge);
+            }
+        }
+
+        if self.backward_lanes > 0 {
+            let points = points.iter().rev().copied().collect::<Vec<Vec3>>();
+            for edge in fit_polyline(&points, &road_type.reversed()) {
+                network.push_edge(&edge);
+            }
+        }
+    }
+}
+
+/// Fits edges through `points`. The road runs through every point in the direction from the
+/// point before it to the point after it, and along the polyline at the ends.
+fn fit_polyline(points: &[Vec3], road_type: &RoadType) -> Vec<RoadEdge> {
+    let last = points.len() - 1;
+    let poses = (0..=last)
+        .map(|index| {
+            let direction = points[(index + 1).min(last)] - points[index.saturating_sub(1)];
+            Transform::from_translation(points[index]).looking_to(direction, Vec3::Y)
+        })
+        .collect::<Vec<Transform>>();
+
+    let mut edges = Vec::new();
+    for pair in poses.windows(2) {
+        let [start, end] = [pair[0], pair[1]];
+        let chord = (end.translation - start.translation).normalize();
+
+        if start.forward().dot(chord) > 1.0 - 1e-4 && end.forward().dot(chord) > 1.0 - 1e-4 {
+            edges.push(RoadEdge::from_start_end(
+                start,
+                end.translation,
+                road_type.clone(),
+            ));
+        } else {
+            let (first, second) = compute_biarc(start, end, road_type.clone());
+            edges.extend([first, second]);
+        }
+    }
+
+    edges
+}
+
+/// Equirectangular projection around an origin, which is accurate enough for a city
+struct Projection {
+    origin: (f64, f64),
+    /// World units per meter
+    scale: f64,
+}
+
+impl Projection {
+    /// Centers the projection on the `<bounds>` of the extract, or else on the `nodes`
+    fn new<'a>(
+        root: Node,
+        nodes: impl Iterator<Item = &'a &'a str>,
+        coordinates: &HashMap<&str, (f64, f64)>,
+    ) -> Self {
+        let bounds = root
+            .children()
+            .find(|node| node.has_tag_name("bounds"))
+            .and_then(|bounds| {
+                let coordinate = |name: &str| {
+                    bounds
+                        .attribute(name)
+                        .and_then(|value| value.parse::<f64>().ok())
+                };
+                Some((
+                    (coordinate("minlat")? + coordinate("maxlat")?) * 0.5,
+                    (coordinate("minlon")? + coordinate("maxlon")?) * 0.5,
+                ))
+            });
+
+        let origin = bounds.unwrap_or_else(|| {
+            let (mut lat, mut lon, mut count) = (0.0, 0.0, 0);
+            for (node_lat, node_lon) in nodes.filter_map(|node| coordinates.get(node)) {
+                lat += node_lat;
+                lon += node_lon;
+                count += 1;
+            }
+            (lat / count.max(1) as f64, lon / count.max(1) as f64)
+        });
+
+        Self {
+            origin,
+            scale: ROAD_WIDTH as f64 / LANE_WIDTH_METERS,
+        }
+    }
+
+    fn project(&self, (lat, lon): (f64, f64)) -> Vec3 {
+        let (origin_lat, origin_lon) = self.origin;
+        let east = (lon - origin_lon).to_radians() * origin_lat.to_radians().cos() * EARTH_RADIUS;
+        let north = (lat - origin_lat).to_radians() * EARTH_RADIUS;
+
+        Vec3::new(
+            (east * self.scale) as f32,
+            0.0,
+            (-north * self.scale) as f32,
+        )
+    }
+}
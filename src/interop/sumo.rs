@@ -0,0 +1,490 @@
+//! SUMO network (`.net.xml`) and route (`.rou.xml`) export, to check the traffic here against
+//! SUMO runs on the same network.
+//!
+//! Lengths and speeds are written in world units, which SUMO takes as meters. SUMO numbers
+//! lanes from the kerb, the other way around from edges here, and is told when traffic keeps
+//! to the left. Junctions are unregulated, as vehicles here don't yield at junctions either,
+//! and have no internal lanes.
+
+use std::fmt::{self, Write};
+
+use bevy::prelude::*;
+
+use crate::{
+    road::{
+        cross_section::DriveSide,
+        edge::{RoadEdge, Twist},
+        graph::{EdgeNodes, RoadGraph},
+        network::{NetworkError, RoadNetwork},
+        route::{find_route, Distance, RoutePoint},
+    },
+    simulation::{
+        clock::SIMULATION_HZ,
+        connector::lane_connections,
+        demand::{DemandError, DemandFile},
+        vehicle::{VehicleType, SPAWN_INTERVAL, TRUCK_SHARE},
+    },
+};
+
+/// Distance between the points of sampled edge shapes
+const SHAPE_SPACING: f32 = 2.0;
+/// Vehicle types written to the route file, with their SUMO ids
+const VEHICLE_TYPES: [(&str, VehicleType); 2] =
+    [("car", VehicleType::CAR), ("truck", VehicleType::TRUCK)];
+
+/// Writes the network as a SUMO network, with a connection for every lane vehicles here may
+/// take from one edge to the next
+pub fn export_network(network: &RoadNetwork) -> Result<String, NetworkError> {
+    let graph = NetworkGraph::new(network)?;
+
+    let mut xml = String::new();
+    write_network(&mut xml, network, &graph).expect("writing to a String can't fail");
+    Ok(xml)
+}
+
+/// Writes the flows of `demand`, and the vehicles the road spawners of the network send off,
+/// as SUMO flows running for `seconds`. Flows follow the route vehicles here take.
+pub fn export_routes(
+    network: &RoadNetwork,
+    demand: &DemandFile,
+    seconds: f32,
+) -> Result<String, SumoError> {
+    let graph = NetworkGraph::new(network)?;
+
+    let mut xml = String::new();
+    write_routes(&mut xml, network, &graph, demand, seconds)?;
+    Ok(xml)
+}
+
+/// Writes a SUMO configuration running the network and route files at the step length and
+/// with the seed of the simulation here
+pub fn export_config(network_file: &str, route_file: &str, seconds: f32, seed: u64) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<configuration>
+    <input>
+        <net-file value="{network_file}"/>
+        <route-files value="{route_file}"/>
+    </input>
+    <time>
+        <begin value="0"/>
+        <end value="{seconds}"/>
+        <step-length value="{}"/>
+    </time>
+    <random_number>
+        <seed value="{seed}"/>
+    </random_number>
+</configuration>
+"#,
+        1.0 / SIMULATION_HZ
+    )
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SumoError {
+    Network(NetworkError),
+    Demand(DemandError),
+}
+
+impl std::fmt::Display for SumoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SumoError::Network(error) => error.fmt(f),
+            SumoError::Demand(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SumoError {}
+
+impl From<NetworkError> for SumoError {
+    fn from(error: NetworkError) -> Self {
+        Self::Network(error)
+    }
+}
+
+impl From<DemandError> for SumoError {
+    fn from(error: DemandError) -> Self {
+        Self::Demand(error)
+    }
+}
+
+/// Road graph of a network file, with stand-in entities for its edges and nodes. Edge `i` is
+/// entity `i`, so routes and turn lanes come out as they do for the spawned network.
+struct NetworkGraph {
+    graph: RoadGraph,
+    edges: Vec<RoadEdge>,
+    drive_side: DriveSide,
+}
+
+impl NetworkGraph {
+    fn new(network: &RoadNetwork) -> Result<Self, NetworkError> {
+        network.validate()?;
+
+        let node = |index: usize| Entity::from_raw((network.edges.len() + index) as u32);
+        let mut graph = RoadGraph::default();
+        for (index, edge) in network.edges.iter().enumerate() {
+            graph.insert_edge(
+                Self::entity(index),
+                EdgeNodes {
+                    start: node(edge.start_node),
+                    end: node(edge.end_node),
+                },
+            );
+        }
+
+        Ok(Self {
+            graph,
+            edges: network.edges.iter().map(|edge| edge.to_edge()).collect(),
+            drive_side: network.drive_side,
+        })
+    }
+
+    fn entity(edge: usize) -> Entity {
+        Entity::from_raw(edge as u32)
+    }
+
+    fn edge(&self, entity: Entity) -> Option<&RoadEdge> {
+        self.edges.get(entity.index() as usize)
+    }
+
+    /// Edges following `edge`, by index
+    fn next_edges(&self, edge: usize) -> impl Iterator<Item = usize> + '_ {
+        self.graph
+            .next_edges(Self::entity(edge))
+            .iter()
+            .map(|entity| entity.index() as usize)
+    }
+
+    /// Edges of the shortest route from the start of `from` to the end of `to`, by index
+    fn route(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        let route = find_route(
+            &self.graph,
+            |entity| self.edge(entity),
+            RoutePoint::new(Self::entity(from), 0.0),
+            RoutePoint::new(Self::entity(to), self.edges.get(to)?.length()),
+            &Distance,
+        )?;
+
+        Some(
+            route
+                .edges()
+                .map(|entity| entity.index() as usize)
+                .collect(),
+        )
+    }
+}
+
+fn write_network(xml: &mut String, network: &RoadNetwork, graph: &NetworkGraph) -> fmt::Result {
+    let shapes = graph
+        .edges
+        .iter()
+        .map(|edge| {
+            (0..edge.lanes())
+                .map(|lane| sample_lane(edge, lane, graph.drive_side))
+                .collect::<Vec<Vec<Vec2>>>()
+        })
+        .collect::<Vec<Vec<Vec<Vec2>>>>();
+
+    let points = shapes
+        .iter()
+        .flatten()
+        .flatten()
+        .copied()
+        .chain(network.nodes.iter().map(|node| sumo_position(*node)));
+    let (min, max) = points.fold(
+        (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+        |(min, max), point| (min.min(point), max.max(point)),
+    );
+    let (min, max) = match min.x <= max.x {
+        true => (min, max),
+        false => (Vec2::ZERO, Vec2::ZERO),
+    };
+
+    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    let lefthand = match network.drive_side {
+        DriveSide::Right => "",
+        DriveSide::Left => r#" lefthand="true""#,
+    };
+    writeln!(
+        xml,
+        r#"<net version="1.16" junctionCornerDetail="0"{lefthand}>"#
+    )?;
+    writeln!(
+        xml,
+        r#"    <location netOffset="0.00,0.00" convBoundary="{:.2},{:.2},{:.2},{:.2}" origBoundary="{:.2},{:.2},{:.2},{:.2}" projParameter="!"/>"#,
+        min.x, min.y, max.x, max.y, min.x, min.y, max.x, max.y
+    )?;
+
+    for (index, (edge, lanes)) in network.edges.iter().zip(&shapes).enumerate() {
+        let road_edge = &graph.edges[index];
+        writeln!(
+            xml,
+            r#"    <edge id="{}" from="{}" to="{}" priority="-1" shape="{}">"#,
+            edge_id(index),
+            node_id(edge.start_node),
+            node_id(edge.end_node),
+            shape(&sample_edge(road_edge)),
+        )?;
+
+        // SUMO lane 0 is the lane at the kerb. Lanes added or dropped along the edge keep
+        // their full width, as SUMO lanes don't taper.
+        let mut widths = edge
+            .road_type
+            .cross_section
+            .driven()
+            .map(|lane| lane.width_at(0.0).max(lane.width_at(1.0)))
+            .collect::<Vec<f32>>();
+        if network.drive_side == DriveSide::Left {
+            widths.reverse();
+        }
+        for (sumo_lane, (lane_shape, width)) in lanes.iter().zip(&widths).rev().enumerate() {
+            writeln!(
+                xml,
+                r#"        <lane id="{}_{sumo_lane}" index="{sumo_lane}" speed="{:.2}" length="{:.2}" width="{:.2}" shape="{}"/>"#,
+                edge_id(index),
+                edge.road_type.speed_limit,
+                edge.length,
+                width,
+                shape(lane_shape),
+            )?;
+        }
+        writeln!(xml, "    </edge>")?;
+    }
+
+    for (index, position) in network.nodes.iter().enumerate() {
+        let incoming = network
+            .edges
+            .iter()
+            .enumerate()
+            .filter(|(_, edge)| edge.end_node == index)
+            .flat_map(|(edge, network_edge)| {
+                (0..network_edge.road_type.lanes())
+                    .map(move |lane| format!("{}_{lane}", edge_id(edge)))
+            })
+            .collect::<Vec<String>>();
+        let outgoing = network.edges.iter().any(|edge| edge.start_node == index);
+        let junction_type = match incoming.is_empty() || !outgoing {
+            true => "dead_end",
+            false => "unregulated",
+        };
+        let position = sumo_position(*position);
+
+        writeln!(
+            xml,
+            r#"    <junction id="{}" type="{junction_type}" x="{:.2}" y="{:.2}" incLanes="{}" intLanes=""/>"#,
+            node_id(index),
+            position.x,
+            position.y,
+            incoming.join(" "),
+        )?;
+    }
+
+    for (index, road_edge) in graph.edges.iter().enumerate() {
+        for next in graph.next_edges(index) {
+            let next_edge = &graph.edges[next];
+            let direction = turn_direction(road_edge, next_edge);
+
+            // The same lanes connect as through the junctions of the simulation
+            for (lane, next_lane) in lane_connections(
+                &graph.graph,
+                |entity| graph.edge(entity),
+                NetworkGraph::entity(index),
+                NetworkGraph::entity(next),
+                graph.drive_side,
+            ) {
+                writeln!(
+                    xml,
+                    r#"    <connection from="{}" to="{}" fromLane="{}" toLane="{}" dir="{direction}" state="M"/>"#,
+                    edge_id(index),
+                    edge_id(next),
+                    road_edge.lanes() - 1 - lane,
+                    next_edge.lanes() - 1 - next_lane,
+                )?;
+            }
+        }
+    }
+
+    writeln!(xml, "</net>")
+}
+
+/// Vehicles sent along a route at a steady rate
+struct RouteFlow {
+    id: String,
+    from: usize,
+    to: usize,
+    vehicles_per_hour: f32,
+    truck_share: f64,
+}
+
+fn write_routes(
+    xml: &mut String,
+    network: &RoadNetwork,
+    graph: &NetworkGraph,
+    demand: &DemandFile,
+    seconds: f32,
+) -> Result<(), SumoError> {
+    let mut flows = Vec::new();
+    for (index, flow) in demand.flows.iter().enumerate() {
+        for edge in [flow.from, flow.to] {
+            if edge >= network.edges.len() {
+                return Err(DemandError::MissingEdge { flow: index, edge }.into());
+            }
+        }
+
+        flows.push(RouteFlow {
+            id: format!("flow_{index}"),
+            from: flow.from,
+            to: flow.to,
+            vehicles_per_hour: flow.vehicles_per_hour,
+            truck_share: flow.truck_share,
+        });
+    }
+
+    // Road spawners send a vehicle to a random dead end from every edge starting at them,
+    // which comes down to an even share of their rate for every dead end
+    let destinations = (0..graph.edges.len())
+        .filter(|&edge| graph.next_edges(edge).next().is_none())
+        .collect::<Vec<usize>>();
+    for (spawner, transform) in network.spawners.iter().enumerate() {
+        let starts = graph
+            .edges
+            .iter()
+            .enumerate()
+            .filter(|(_, edge)| edge.start().translation.distance(transform.translation) < 0.01)
+            .map(|(index, _)| index);
+
+        for start in starts {
+            for &destination in &destinations {
+                flows.push(RouteFlow {
+                    id: format!("spawner_{spawner}_{start}_{destination}"),
+                    from: start,
+                    to: destination,
+                    vehicles_per_hour: 3600.0 / SPAWN_INTERVAL / destinations.len() as f32,
+                    truck_share: TRUCK_SHARE,
+                });
+            }
+        }
+    }
+
+    write_flows(xml, graph, &flows, seconds).expect("writing to a String can't fail");
+    Ok(())
+}
+
+fn write_flows(
+    xml: &mut String,
+    graph: &NetworkGraph,
+    flows: &[RouteFlow],
+    seconds: f32,
+) -> fmt::Result {
+    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(xml, "<routes>")?;
+
+    for (id, vehicle_type) in VEHICLE_TYPES {
+        let idm = vehicle_type.idm;
+        writeln!(
+            xml,
+            r#"    <vType id="{id}" length="{:.2}" minGap="{:.2}" accel="{:.2}" decel="{:.2}" tau="{:.2}" maxSpeed="{:.2}" delta="{}" speedDev="0" carFollowModel="IDM"/>"#,
+            vehicle_type.length,
+            idm.minimum_gap,
+            idm.max_acceleration,
+            idm.comfortable_deceleration,
+            idm.time_headway,
+            idm.desired_speed,
+            idm.exponent,
+        )?;
+    }
+
+    for flow in flows {
+        let id = &flow.id;
+        let Some(route) = graph.route(flow.from, flow.to) else {
+            writeln!(
+                xml,
+                "    <!-- {id}: no route from {} to {} -->",
+                edge_id(flow.from),
+                edge_id(flow.to)
+            )?;
+            continue;
+        };
+        let edges = route
+            .into_iter()
+            .map(edge_id)
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        writeln!(xml, r#"    <route id="{id}" edges="{edges}"/>"#)?;
+        writeln!(
+            xml,
+            r#"    <vTypeDistribution id="{id}_types" vTypes="car truck" probabilities="{} {}"/>"#,
+            1.0 - flow.truck_share,
+            flow.truck_share
+        )?;
+        writeln!(
+            xml,
+            r#"    <flow id="{id}" type="{id}_types" route="{id}" begin="0" end="{seconds}" vehsPerHour="{}" departLane="random" departSpeed="desired"/>"#,
+            flow.vehicles_per_hour
+        )?;
+    }
+
+    writeln!(xml, "</routes>")
+}
+
+fn edge_id(index: usize) -> String {
+    format!("e{index}")
+}
+
+fn node_id(index: usize) -> String {
+    format!("n{index}")
+}
+
+/// SUMO `x` and `y` of a world position, with `y` pointing along -Z
+fn sumo_position(position: Vec3) -> Vec2 {
+    Vec2::new(position.x, -position.z)
+}
+
+fn samples(edge: &RoadEdge) -> impl Iterator<Item = f32> {
+    let segments = match edge.twist() {
+        Twist::Straight => 1,
+        _ => (edge.length() / SHAPE_SPACING).ceil().max(1.0) as u32,
+    };
+    let length = edge.length();
+
+    (0..=segments).map(move |segment| length * segment as f32 / segments as f32)
+}
+
+fn sample_edge(edge: &RoadEdge) -> Vec<Vec2> {
+    samples(edge)
+        .map(|length| sumo_position(edge.interpolate(length).translation))
+        .collect()
+}
+
+fn sample_lane(edge: &RoadEdge, lane: u8, side: DriveSide) -> Vec<Vec2> {
+    samples(edge)
+        .map(|length| sumo_position(edge.interpolate_lane(length, lane as i32, side).translation))
+        .collect()
+}
+
+fn shape(points: &[Vec2]) -> String {
+    points
+        .iter()
+        .map(|point| format!("{:.2},{:.2}", point.x, point.y))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// SUMO direction of the turn from `edge` onto `next`
+fn turn_direction(edge: &RoadEdge, next: &RoadEdge) -> &'static str {
+    let end = edge.end();
+    let direction = *next.end().forward();
+    let angle = direction
+        .dot(*end.left())
+        .atan2(direction.dot(*end.forward()))
+        .to_degrees();
+
+    match angle {
+        angle if angle.abs() > 150.0 => "t",
+        angle if angle > 30.0 => "l",
+        angle if angle < -30.0 => "r",
+        _ => "s",
+    }
+}
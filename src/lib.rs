@@ -0,0 +1,7 @@
+pub mod camera;
+pub mod cli;
+pub mod interop;
+pub mod raycast;
+pub mod road;
+pub mod simulation;
+pub mod states;
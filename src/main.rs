@@ -1,26 +1,44 @@
 use std::f32::consts::PI;
 
 use bevy::prelude::*;
-use camera::{CameraPlugin, PanOrbitCamera};
+use bevy_road::{
+    camera::{CameraPlugin, PanOrbitCamera},
+    road::{asset::NetworkInstance, RoadPlugin, RoadSpawner},
+    simulation::{clock::SimulationControlsPlugin, vehicle::VehicleMeshPlugin, SimulationPlugin},
+    states::GameStatePlugin,
+};
 use debug::DebugPlugin;
-use road::{RoadPlugin, RoadSpawner};
-use states::GameStatePlugin;
 
-pub mod camera;
 mod debug;
-pub mod raycast;
-pub mod road;
-pub mod states;
 
 fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
         .add_plugins((CameraPlugin, GameStatePlugin, RoadPlugin))
+        .add_plugins((
+            SimulationPlugin,
+            SimulationControlsPlugin,
+            VehicleMeshPlugin,
+        ))
         .add_plugins(DebugPlugin)
-        .add_systems(Startup, setup_scene)
+        .add_systems(Startup, (setup_scene, load_network_argument))
         .run();
 }
 
+/// Shows the network file passed on the command line, such as `networks/junction.road.ron`
+/// in the assets folder, or an `.xodr` or `.osm` file to import. The network follows any
+/// changes made to the file.
+fn load_network_argument(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let Some(path) = std::env::args().nth(1) else {
+        return;
+    };
+
+    commands.spawn((
+        Name::new("Road Network"),
+        NetworkInstance::new(asset_server.load(path)),
+    ));
+}
+
 fn setup_scene(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
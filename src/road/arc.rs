@@ -2,7 +2,7 @@ use std::f32::consts::{PI, TAU};
 
 use bevy::{math::bounding::Aabb3d, prelude::*};
 
-use super::{RoadEdge, ROAD_WIDTH};
+use super::{collision::Centerline, RoadEdge, ROAD_WIDTH};
 
 #[derive(Debug, Default, Reflect, Clone, Copy, PartialEq)]
 pub enum Twist {
@@ -53,7 +53,7 @@ impl ArcEdge {
 
     pub fn from_start_end(start: Vec2, tangent: Vec2, end: Vec2, lanes: u8) -> Result<Self, ()> {
         let chord = end - start;
-        let normal = start.perp();
+        let normal = tangent.perp();
 
         let scalar = chord.dot(normal);
         // Straight line
@@ -102,6 +102,32 @@ impl ArcEdge {
     pub fn radius(&self) -> f32 {
         self.radius
     }
+
+    pub fn start(&self) -> Vec2 {
+        self.start
+    }
+
+    pub fn end(&self) -> Vec2 {
+        self.centerline().end()
+    }
+
+    pub fn twist(&self) -> Twist {
+        self.twist
+    }
+
+    pub fn centerline(&self) -> Centerline {
+        let sweep = match self.twist {
+            Twist::CounterClockwise => self.length / self.radius,
+            Twist::Clockwise => -self.length / self.radius,
+        };
+
+        Centerline::Arc {
+            center: self.center,
+            radius: self.radius,
+            start: self.start,
+            sweep,
+        }
+    }
 }
 
 impl RoadEdge for ArcEdge {
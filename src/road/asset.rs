@@ -0,0 +1,165 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::{BoxedFuture, HashSet},
+};
+
+use crate::interop;
+
+use super::network::{NetworkError, RoadNetwork};
+
+pub struct RoadNetworkAssetPlugin;
+impl Plugin for RoadNetworkAssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<RoadNetwork>()
+            .register_asset_loader(RoadNetworkLoader)
+            .add_systems(Update, spawn_network_assets);
+    }
+}
+
+/// Loads `.road.ron` network files, in any version, and imports OpenDRIVE and OpenStreetMap
+/// files
+#[derive(Debug, Default)]
+pub struct RoadNetworkLoader;
+
+impl AssetLoader for RoadNetworkLoader {
+    type Asset = RoadNetwork;
+    type Settings = ();
+    type Error = NetworkError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<RoadNetwork, NetworkError>> {
+        Box::pin(async move {
+            let mut text = String::new();
+            reader
+                .read_to_string(&mut text)
+                .await
+                .map_err(|error| NetworkError::Io(error.to_string()))?;
+
+            let network = interop::read_network(&text, load_context.path())?;
+            network.validate()?;
+
+            Ok(network)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["road.ron", "xodr", "osm"]
+    }
+}
+
+/// Spawns the network of a road network asset. The network is spawned again whenever the
+/// asset changes, for instance when the file is edited on disk.
+#[derive(Component, Debug, Default)]
+pub struct NetworkInstance {
+    pub handle: Handle<RoadNetwork>,
+    /// Whether the network of the asset has been spawned
+    spawned: bool,
+}
+
+impl NetworkInstance {
+    pub fn new(handle: Handle<RoadNetwork>) -> Self {
+        Self {
+            handle,
+            spawned: false,
+        }
+    }
+}
+
+/// Marks the nodes, edges and spawners of the network of an asset, which are replaced when the
+/// asset changes. Edges split off from or merged out of a marked edge carry the mark over.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FromNetworkAsset(pub AssetId<RoadNetwork>);
+
+/// Gives `piece` the network asset mark of `edge`, if it has one. For edges and nodes made out
+/// of `edge`, which has to still exist when the commands are applied.
+pub fn inherit_network_asset(commands: &mut Commands, edge: Entity, piece: Entity) {
+    commands.add(move |world: &mut World| {
+        let Some(mark) = world.get::<FromNetworkAsset>(edge).copied() else {
+            return;
+        };
+        if let Some(mut piece) = world.get_entity_mut(piece) {
+            piece.insert(mark);
+        }
+    });
+}
+
+fn spawn_network_assets(
+    mut events: EventReader<AssetEvent<RoadNetwork>>,
+    networks: Res<Assets<RoadNetwork>>,
+    mut instances: Query<&mut NetworkInstance>,
+    marked: Query<(Entity, &FromNetworkAsset)>,
+    mut commands: Commands,
+) {
+    let changed = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect::<HashSet<AssetId<RoadNetwork>>>();
+
+    for mut instance in &mut instances {
+        let id = instance.handle.id();
+        if instance.spawned && !changed.contains(&id) {
+            continue;
+        }
+
+        let Some(network) = networks.get(id) else {
+            continue;
+        };
+
+        for (entity, FromNetworkAsset(asset)) in &marked {
+            if *asset == id {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+
+        commands.insert_resource(network.drive_side);
+        match network.spawn(&mut commands) {
+            Ok(spawned) => {
+                let entities = spawned
+                    .nodes
+                    .into_iter()
+                    .chain(spawned.edges)
+                    .chain(spawned.spawners);
+                for entity in entities {
+                    commands.entity(entity).insert(FromNetworkAsset(id));
+                }
+                instance.spawned = true;
+            }
+            Err(error) => error!("Could not spawn road network: {error}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::CommandQueue;
+
+    use super::*;
+
+    #[test]
+    fn pieces_inherit_the_mark_of_their_edge() {
+        let mut world = World::new();
+        let mark = FromNetworkAsset(AssetId::default());
+        let edge = world.spawn(mark).id();
+        let unmarked = world.spawn_empty().id();
+        let piece = world.spawn_empty().id();
+        let other_piece = world.spawn_empty().id();
+
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        inherit_network_asset(&mut commands, edge, piece);
+        inherit_network_asset(&mut commands, unmarked, other_piece);
+        commands.entity(edge).despawn();
+        queue.apply(&mut world);
+
+        assert_eq!(world.get::<FromNetworkAsset>(piece), Some(&mark));
+        assert_eq!(world.get::<FromNetworkAsset>(other_piece), None);
+    }
+}
@@ -1,15 +1,32 @@
 use bevy::prelude::*;
 
-use super::edge::RoadEdge;
+use super::{edge::RoadEdge, palette::RoadType};
 
-pub fn compute_biarc(start: Transform, end: Transform, lanes: u8) -> (RoadEdge, RoadEdge) {
+pub fn compute_biarc(
+    start: Transform,
+    end: Transform,
+    road_type: RoadType,
+) -> (RoadEdge, RoadEdge) {
     let midpoint = compute_midpoint(start, end);
 
-    let edge1 = RoadEdge::from_start_end(start, midpoint, lanes);
+    let edge1 = RoadEdge::from_start_end(start, midpoint, road_type.clone());
 
     let mid_transform = edge1.end();
-    let edge2 = RoadEdge::from_start_end(mid_transform, end.translation, lanes);
-    (edge1, edge2)
+    let edge2 = RoadEdge::from_start_end(mid_transform, end.translation, road_type.clone());
+    if !road_type.cross_section.is_tapered() {
+        return (edge1, edge2);
+    }
+
+    // Lanes added or dropped along the biarc change width evenly over both arcs
+    let along = edge1.length() / (edge1.length() + edge2.length()).max(f32::EPSILON);
+    (
+        RoadEdge::from_start_end(start, midpoint, road_type.between(0.0, along)),
+        RoadEdge::from_start_end(
+            mid_transform,
+            end.translation,
+            road_type.between(along, 1.0),
+        ),
+    )
 }
 
 fn compute_midpoint(start: Transform, end: Transform) -> Vec3 {
@@ -27,7 +44,7 @@ fn compute_midpoint(start: Transform, end: Transform) -> Vec3 {
     // if the special case d is infinity, the only solution is to
     // interpolate across two semicircles
     if denominator < 0.001 && v.dot(end_tangent).abs() < 0.001 {
-        return v.extend(0.0).xzy() * 0.5;
+        return (startpoint + v * 0.5).extend(0.0).xzy();
     }
 
     // if the quadratic formula denominator is zero, the tangents are equal
@@ -0,0 +1,373 @@
+use bevy::{
+    input::common_conditions::{input_just_pressed, input_just_released},
+    prelude::*,
+    utils::HashSet,
+};
+
+use crate::{raycast::Raycast, states::GameState};
+
+use super::{
+    asset::inherit_network_asset,
+    edge::{RoadEdge, Twist},
+    graph::{EdgeNodes, RoadGraph},
+    history::BuildHistory,
+    placeholder::{BuildSystemSet, BuildTool, RoadPlaceholder},
+    world::WorldTile,
+    ROAD_WIDTH,
+};
+
+/// Drags shorter than this delete the edge under the cursor instead of a range
+const DRAG_THRESHOLD: f32 = 0.5 * ROAD_WIDTH;
+
+/// How far edges may be from running on as one line or circle and still be merged
+const MERGE_TOLERANCE: f32 = 1e-3;
+
+pub struct BulldozePlugin;
+impl Plugin for BulldozePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BulldozeSelection>()
+            .add_systems(
+                Update,
+                toggle_bulldozer
+                    .run_if(input_just_released(KeyCode::KeyB))
+                    .run_if(in_state(GameState::Building)),
+            )
+            .add_systems(
+                Update,
+                (
+                    start_selection.run_if(input_just_pressed(MouseButton::Left)),
+                    draw_selection,
+                    bulldoze.run_if(input_just_released(MouseButton::Left)),
+                )
+                    .chain()
+                    .in_set(BuildSystemSet::Bulldozing),
+            );
+    }
+}
+
+type EdgeQuery<'w, 's> =
+    Query<'w, 's, (Entity, &'static RoadEdge, &'static EdgeNodes), Without<RoadPlaceholder>>;
+
+/// Where the current drag of the bulldozer started
+#[derive(Resource, Debug, Default)]
+struct BulldozeSelection {
+    start: Option<Vec3>,
+}
+
+/// Switches between placing roads and bulldozing them with B, dropping the road being placed
+fn toggle_bulldozer(
+    mut tool: ResMut<BuildTool>,
+    mut selection: ResMut<BulldozeSelection>,
+    placeholders: Query<Entity, With<RoadPlaceholder>>,
+    mut commands: Commands,
+) {
+    *tool = match *tool {
+        BuildTool::Bulldoze => BuildTool::Road,
+        _ => BuildTool::Bulldoze,
+    };
+    selection.start = None;
+
+    for entity in &placeholders {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn start_selection(world_cast: Raycast<With<WorldTile>>, mut selection: ResMut<BulldozeSelection>) {
+    selection.start = world_cast.cursor_ray().map(|(_, hitpoint)| hitpoint);
+}
+
+fn draw_selection(
+    world_cast: Raycast<With<WorldTile>>,
+    selection: Res<BulldozeSelection>,
+    mut gizmos: Gizmos,
+) {
+    let (Some(start), Some((_, end))) = (selection.start, world_cast.cursor_ray()) else {
+        return;
+    };
+
+    if start.distance(end) < DRAG_THRESHOLD {
+        return;
+    }
+
+    gizmos.linestrip(
+        [
+            start,
+            Vec3::new(end.x, start.y, start.z),
+            end,
+            Vec3::new(start.x, start.y, end.z),
+            start,
+        ],
+        Color::RED,
+    );
+}
+
+/// Deletes the edge under the cursor, or every edge in the dragged rectangle, along with the
+/// other direction of two-way roads. Where that leaves a node joining just two edges that run
+/// on as one, or the two directions of two such two-way roads, they are merged.
+fn bulldoze(
+    world_cast: Raycast<With<WorldTile>>,
+    world_tiles: Query<&WorldTile>,
+    edges: EdgeQuery,
+    graph: Res<RoadGraph>,
+    mut selection: ResMut<BulldozeSelection>,
+    mut history: ResMut<BuildHistory>,
+    mut commands: Commands,
+) {
+    let Some((tile_entity, end)) = world_cast.cursor_ray() else {
+        return;
+    };
+    let start = selection.start.take().unwrap_or(end);
+
+    let doomed = match start.distance(end) < DRAG_THRESHOLD {
+        true => world_tiles
+            .get(tile_entity)
+            .unwrap()
+            .edges
+            .iter()
+            .filter_map(|entity| edges.get(*entity).ok())
+            .filter(|(_, edge, _)| edge.intersects_point(end))
+            .min_by(|(_, a, _), (_, b, _)| {
+                let distance = |edge: &RoadEdge| edge.centerline().distance_to_point(end.xz());
+                distance(a).total_cmp(&distance(b))
+            })
+            .map(|(entity, _, _)| entity)
+            .into_iter()
+            .collect::<Vec<Entity>>(),
+        false => {
+            let min = start.xz().min(end.xz());
+            let max = start.xz().max(end.xz());
+
+            edges
+                .iter()
+                .filter(|(_, edge, _)| {
+                    let samples = (edge.length() / ROAD_WIDTH).ceil().max(1.0) as u32;
+                    (0..=samples).any(|sample| {
+                        let length = edge.length() * sample as f32 / samples as f32;
+                        let point = edge.interpolate(length).translation.xz();
+                        point.cmpge(min).all() && point.cmple(max).all()
+                    })
+                })
+                .map(|(entity, _, _)| entity)
+                .collect()
+        }
+    };
+
+    if doomed.is_empty() {
+        return;
+    }
+
+    history.record(BuildHistory::snapshot(&graph, |entity| {
+        edges.get(entity).ok().map(|(_, edge, _)| edge)
+    }));
+
+    let mut remaining = RemainingEdges {
+        graph: &graph,
+        edges: &edges,
+        removed: HashSet::new(),
+        merged: Vec::new(),
+        origins: Vec::new(),
+    };
+
+    let twins = doomed
+        .iter()
+        .filter_map(|entity| {
+            graph.twin(
+                |entity| edges.get(entity).ok().map(|(_, edge, _)| edge),
+                *entity,
+            )
+        })
+        .collect::<Vec<Entity>>();
+
+    let mut nodes = Vec::new();
+    for entity in doomed.into_iter().chain(twins) {
+        if let Ok((_, _, edge_nodes)) = edges.get(entity) {
+            nodes.extend([edge_nodes.start, edge_nodes.end]);
+        }
+        remaining.removed.insert(entity);
+    }
+    nodes.sort();
+    nodes.dedup();
+
+    for node in nodes {
+        remaining.merge_at(node);
+    }
+
+    // Merged edges take the network asset mark over before the edges they replace are gone
+    for (merged, origin) in remaining.merged.into_iter().zip(remaining.origins) {
+        let Some((edge, edge_nodes)) = merged else {
+            continue;
+        };
+        let entity = commands
+            .spawn((Name::new("Road Edge"), edge, edge_nodes))
+            .id();
+        inherit_network_asset(&mut commands, origin, entity);
+    }
+
+    for entity in remaining.removed {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// An edge still in the world, or one merged from others that is yet to be spawned
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Piece {
+    Existing(Entity),
+    Merged(usize),
+}
+
+/// The edges as they will be once the bulldozer is done
+struct RemainingEdges<'a, 'w, 's> {
+    graph: &'a RoadGraph,
+    edges: &'a EdgeQuery<'w, 's>,
+    removed: HashSet<Entity>,
+    merged: Vec<Option<(RoadEdge, EdgeNodes)>>,
+    /// Edge in the world each merged edge starts with
+    origins: Vec<Entity>,
+}
+
+impl RemainingEdges<'_, '_, '_> {
+    fn get(&self, piece: Piece) -> Option<(RoadEdge, EdgeNodes)> {
+        match piece {
+            Piece::Existing(entity) => self
+                .edges
+                .get(entity)
+                .ok()
+                .map(|(_, edge, nodes)| (edge.clone(), *nodes)),
+            Piece::Merged(index) => self.merged[index].clone(),
+        }
+    }
+
+    fn origin(&self, piece: Piece) -> Entity {
+        match piece {
+            Piece::Existing(entity) => entity,
+            Piece::Merged(index) => self.origins[index],
+        }
+    }
+
+    fn remove(&mut self, piece: Piece) {
+        match piece {
+            Piece::Existing(entity) => {
+                self.removed.insert(entity);
+            }
+            Piece::Merged(index) => self.merged[index] = None,
+        }
+    }
+
+    /// Edges arriving at and leaving `node`
+    fn around(&self, node: Entity) -> (Vec<Piece>, Vec<Piece>) {
+        let existing = |edges: &[Entity]| {
+            edges
+                .iter()
+                .filter(|entity| !self.removed.contains(*entity))
+                .map(|entity| Piece::Existing(*entity))
+                .collect::<Vec<Piece>>()
+        };
+
+        let mut incoming = existing(self.graph.incoming(node));
+        let mut outgoing = existing(self.graph.outgoing(node));
+        for (index, merged) in self.merged.iter().enumerate() {
+            let Some((_, nodes)) = merged else {
+                continue;
+            };
+
+            if nodes.end == node {
+                incoming.push(Piece::Merged(index));
+            }
+            if nodes.start == node {
+                outgoing.push(Piece::Merged(index));
+            }
+        }
+
+        (incoming, outgoing)
+    }
+
+    /// Whether `first` runs back along `second`, as the two directions of a two-way road do
+    fn twins(&self, first: Piece, second: Piece) -> bool {
+        match (self.get(first), self.get(second)) {
+            (Some((first, _)), Some((second, _))) => first.is_reverse_of(&second),
+            _ => false,
+        }
+    }
+
+    /// Replaces the two edges meeting at `node` by one, if they are all that meet there and
+    /// they run on as one line or circle. Two-way roads merge both directions, or neither.
+    fn merge_at(&mut self, node: Entity) {
+        let (incoming, outgoing) = self.around(node);
+        let pairs = match (incoming.as_slice(), outgoing.as_slice()) {
+            ([first], [second]) => vec![(*first, *second)],
+            // Each edge arriving goes on along the twin of the other one
+            ([a, b], [c, d]) if self.twins(*a, *d) && self.twins(*b, *c) => {
+                vec![(*a, *c), (*b, *d)]
+            }
+            ([a, b], [c, d]) if self.twins(*a, *c) && self.twins(*b, *d) => {
+                vec![(*a, *d), (*b, *c)]
+            }
+            _ => return,
+        };
+
+        let mut merged = Vec::new();
+        for (first, second) in &pairs {
+            let (Some((first_edge, first_nodes)), Some((second_edge, second_nodes))) =
+                (self.get(*first), self.get(*second))
+            else {
+                return;
+            };
+
+            // A loop would close into a full circle
+            if second_nodes.end == first_nodes.start {
+                return;
+            }
+
+            let Some(edge) = merge(&first_edge, &second_edge) else {
+                return;
+            };
+
+            merged.push(Some((
+                edge,
+                EdgeNodes {
+                    start: first_nodes.start,
+                    end: second_nodes.end,
+                },
+            )));
+        }
+
+        for (first, second) in pairs {
+            self.origins.push(self.origin(first));
+            self.remove(first);
+            self.remove(second);
+        }
+        self.merged.extend(merged);
+    }
+}
+
+/// The single edge `first` and `second` make up, if they are the same type of road and run on
+/// as one line or one circle. Edges that add or drop lanes stay as they are.
+fn merge(first: &RoadEdge, second: &RoadEdge) -> Option<RoadEdge> {
+    if first.road_type() != second.road_type()
+        || first.road_type().cross_section.is_tapered()
+        || first.twist() != second.twist()
+    {
+        return None;
+    }
+
+    let tangent = first.end().forward().dot(*second.start().forward());
+    if tangent < 1.0 - MERGE_TOLERANCE {
+        return None;
+    }
+
+    if first.twist() != Twist::Straight
+        && (first.radius() - second.radius()).abs() > MERGE_TOLERANCE * first.radius()
+    {
+        return None;
+    }
+
+    let merged = RoadEdge::from_start_end(
+        first.start(),
+        second.end().translation,
+        first.road_type().clone(),
+    );
+
+    // Arcs that go around more than once can't be a single edge
+    let length = first.length() + second.length();
+    ((merged.length() - length).abs() < MERGE_TOLERANCE * length).then_some(merged)
+}
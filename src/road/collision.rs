@@ -1,8 +1,13 @@
+use std::f32::consts::TAU;
+
 use bevy::{math::bounding::IntersectsVolume, prelude::*};
 
 use super::{arc::ArcEdge, line::LineEdge, RoadEdge, ROAD_WIDTH};
 
-trait EdgeCollision {
+/// Distance under which two points are considered to be the same point
+const EPSILON: f32 = 1e-4;
+
+pub trait EdgeCollision {
     fn collides_arc(&self, other: &ArcEdge) -> bool;
     fn collides_line(&self, other: &LineEdge) -> bool;
 
@@ -16,166 +21,430 @@ impl EdgeCollision for ArcEdge {
             return false;
         }
 
-        let dist = self.center().distance(other.center());
         let half_width_self = self.lanes() as f32 * ROAD_WIDTH * 0.5;
         let half_width_other = other.lanes() as f32 * ROAD_WIDTH * 0.5;
-        let (self_radius, other_radius) = match dist < (self.radius() - other.radius()).abs() {
-            true => match self.radius() > other.radius() {
-                true => (
-                    self.radius() - half_width_self,
-                    other.radius() + half_width_other,
-                ),
-                false => (
-                    self.radius() + half_width_self,
-                    other.radius() - half_width_other,
-                ),
-            },
-            false => (
-                self.radius() + half_width_self,
-                other.radius() + half_width_other,
-            ),
-        };
 
-        if let Some((i1, i2)) =
-            circle_circle_intersections(self.center(), self_radius, other.center(), other_radius)
-        {
-            if self.intersects_point(i1) && other.intersects_point(i1) {
-                return true;
-            }
+        self.centerline().distance(&other.centerline()) <= half_width_self + half_width_other
+    }
 
-            if self.intersects_point(i2) && other.intersects_point(i2) {
-                return true;
-            }
+    fn collides_line(&self, other: &LineEdge) -> bool {
+        if !self.aabb3().intersects(&other.aabb3()) {
+            return false;
         }
 
-        false
-    }
+        let half_width_self = self.lanes() as f32 * ROAD_WIDTH * 0.5;
+        let half_width_other = other.lanes() as f32 * ROAD_WIDTH * 0.5;
 
-    fn collides_line(&self, other: &LineEdge) -> bool {
-        todo!()
+        self.centerline().distance(&other.centerline()) <= half_width_self + half_width_other
     }
 
     fn intersects_arc(&self, other: &ArcEdge) -> (Option<Vec2>, Option<Vec2>) {
-        if let Some((i1, i2)) = circle_circle_intersections(
-            self.center(),
-            self.radius(),
-            other.center(),
-            other.radius(),
-        ) {
-            let s1 = match self.intersects_point(i1) && other.intersects_point(i1) {
-                true => Some(i1),
-                false => None,
-            };
-
-            let s2 = match self.intersects_point(i2) && other.intersects_point(i2) {
-                true => Some(i2),
-                false => None,
-            };
-
-            return (s1, s2);
-        }
-
-        (None, None)
+        self.centerline().intersections(&other.centerline())
     }
 
     fn intersects_line(&self, other: &LineEdge) -> (Option<Vec2>, Option<Vec2>) {
-        todo!()
+        self.centerline().intersections(&other.centerline())
     }
 }
 
 impl EdgeCollision for LineEdge {
     fn collides_arc(&self, other: &ArcEdge) -> bool {
-        todo!()
+        other.collides_line(self)
     }
 
     fn collides_line(&self, other: &LineEdge) -> bool {
+        if !self.aabb3().intersects(&other.aabb3()) {
+            return false;
+        }
+
         let width1 = self.lanes() as f32 * ROAD_WIDTH * 0.5;
         let width2 = other.lanes() as f32 * ROAD_WIDTH * 0.5;
-        (ray_ray_distance(self, other) - (width1 - width2)).is_sign_positive()
+
+        self.centerline().distance(&other.centerline()) <= width1 + width2
     }
 
     fn intersects_arc(&self, other: &ArcEdge) -> (Option<Vec2>, Option<Vec2>) {
-        todo!()
+        self.centerline().intersections(&other.centerline())
     }
 
     fn intersects_line(&self, other: &LineEdge) -> (Option<Vec2>, Option<Vec2>) {
-        todo!()
+        self.centerline().intersections(&other.centerline())
     }
 }
 
-fn ray_ray_distance(self_edge: &LineEdge, other: &LineEdge) -> f32 {
-    // Step 1: Calculate intersection point of lines
-    let intersection = line_intersection(self_edge, other);
+/// The centerline of an edge on the XZ plane, stripped down to its geometric primitive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Centerline {
+    Segment {
+        start: Vec2,
+        end: Vec2,
+    },
+    /// `sweep` is the signed angle between `start` and the end of the arc, measured around `center`.
+    Arc {
+        center: Vec2,
+        radius: f32,
+        start: Vec2,
+        sweep: f32,
+    },
+}
 
-    if let Some(point) = intersection {
-        if is_point_on_ray(self_edge, point) && is_point_on_ray(other, point) {
-            return 0.0;
+impl Centerline {
+    pub fn start(&self) -> Vec2 {
+        match *self {
+            Centerline::Segment { start, .. } => start,
+            Centerline::Arc { start, .. } => start,
         }
     }
 
-    let dist1 = match is_point_on_ray(self_edge, other.start()) {
-        true => Some(point_to_line_distance(self_edge, other.start())),
-        false => None,
-    };
+    pub fn end(&self) -> Vec2 {
+        match *self {
+            Centerline::Segment { end, .. } => end,
+            Centerline::Arc {
+                center,
+                start,
+                sweep,
+                ..
+            } => center + Vec2::from_angle(sweep).rotate(start - center),
+        }
+    }
 
-    let dist2 = match is_point_on_ray(self_edge, other.end()) {
-        true => Some(point_to_line_distance(self_edge, other.end())),
-        false => None,
-    };
+    pub fn length(&self) -> f32 {
+        match *self {
+            Centerline::Segment { start, end } => start.distance(end),
+            Centerline::Arc { radius, sweep, .. } => radius * sweep.abs(),
+        }
+    }
 
-    let dist3 = match is_point_on_ray(other, self_edge.start()) {
-        true => Some(point_to_line_distance(other, self_edge.start())),
-        false => None,
-    };
+    /// Length along the centerline of the point on it closest to `point`
+    pub fn project(&self, point: Vec2) -> f32 {
+        match *self {
+            Centerline::Segment { start, end } => {
+                let length = start.distance(end);
+                if length < EPSILON {
+                    return 0.0;
+                }
+
+                ((end - start) / length)
+                    .dot(point - start)
+                    .clamp(0.0, length)
+            }
+            Centerline::Arc { radius, .. } => match self.arc_angle(point) {
+                Some(angle) => angle * radius,
+                None => match point.distance(self.start()) <= point.distance(self.end()) {
+                    true => 0.0,
+                    false => self.length(),
+                },
+            },
+        }
+    }
 
-    let dist4 = match is_point_on_ray(other, self_edge.end()) {
-        true => Some(point_to_line_distance(other, self_edge.end())),
-        false => None,
-    };
+    pub fn distance_to_point(&self, point: Vec2) -> f32 {
+        point.distance(self.closest_point(point))
+    }
+
+    /// Point on the centerline closest to `point`
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        match *self {
+            Centerline::Segment { start, end } => closest_point_on_segment(point, start, end),
+            Centerline::Arc { center, radius, .. } => match self.arc_angle(point) {
+                Some(_) if center.distance(point) > EPSILON => {
+                    center + (point - center).normalize() * radius
+                }
+                _ => match point.distance(self.start()) <= point.distance(self.end()) {
+                    true => self.start(),
+                    false => self.end(),
+                },
+            },
+        }
+    }
+
+    /// Points where both centerlines cross or touch. Overlapping collinear or co-circular
+    /// centerlines return the start and end of the shared stretch.
+    pub fn intersections(&self, other: &Centerline) -> (Option<Vec2>, Option<Vec2>) {
+        let points = match (*self, *other) {
+            (
+                Centerline::Segment { start: a0, end: a1 },
+                Centerline::Segment { start: b0, end: b1 },
+            ) => segment_segment_intersections(a0, a1, b0, b1),
+            (Centerline::Segment { start, end }, Centerline::Arc { center, radius, .. }) => {
+                let (i1, i2) = circle_segment_intersections(center, radius, start, end);
+                (
+                    i1.filter(|p| other.arc_angle(*p).is_some()),
+                    i2.filter(|p| other.arc_angle(*p).is_some()),
+                )
+            }
+            (Centerline::Arc { .. }, Centerline::Segment { .. }) => other.intersections(self),
+            (
+                Centerline::Arc {
+                    center: c1,
+                    radius: r1,
+                    ..
+                },
+                Centerline::Arc {
+                    center: c2,
+                    radius: r2,
+                    ..
+                },
+            ) => {
+                if c1.distance(c2) < EPSILON && (r1 - r2).abs() < EPSILON {
+                    // Co-circular arcs only meet where one arc holds an end point of the other
+                    let mut overlap = [self.start(), self.end(), other.start(), other.end()]
+                        .into_iter()
+                        .filter(|p| self.arc_angle(*p).is_some() && other.arc_angle(*p).is_some());
+
+                    let first = overlap.next();
+                    let second = overlap.find(|p| first.is_some_and(|f| f.distance(*p) > EPSILON));
+                    (first, second)
+                } else {
+                    match circle_circle_intersections(c1, r1, c2, r2) {
+                        Some((i1, i2)) => (
+                            Some(i1).filter(|p| {
+                                self.arc_angle(*p).is_some() && other.arc_angle(*p).is_some()
+                            }),
+                            Some(i2).filter(|p| {
+                                self.arc_angle(*p).is_some() && other.arc_angle(*p).is_some()
+                            }),
+                        ),
+                        None => (None, None),
+                    }
+                }
+            }
+        };
 
-    let distances = [dist1, dist2, dist3, dist4]
-        .iter()
-        .filter_map(|x| *x)
-        .collect::<Vec<f32>>();
-
-    match distances.is_empty() {
-        false => *distances.iter().min_by(|a, b| a.total_cmp(b)).unwrap(),
-        true => {
-            let dist1 = (self_edge.start() - other.start()).length_squared();
-            let dist2 = (self_edge.start() - other.end()).length_squared();
-            let dist3 = (self_edge.end() - other.start()).length_squared();
-            let dist4 = (self_edge.end() - other.end()).length_squared();
-
-            [dist1, dist2, dist3, dist4]
-                .iter()
-                .min_by(|a, b| a.total_cmp(b))
-                .unwrap()
-                .sqrt()
+        match points {
+            (None, Some(p)) => (Some(p), None),
+            (Some(p1), Some(p2)) if p1.distance(p2) < EPSILON => (Some(p1), None),
+            points => points,
         }
     }
-}
 
-fn line_intersection(r1: &LineEdge, r2: &LineEdge) -> Option<Vec2> {
-    let denominator = r1.tangent().perp_dot(r2.tangent());
+    /// Smallest distance between both centerlines, zero when they cross or touch
+    pub fn distance(&self, other: &Centerline) -> f32 {
+        let (point, other_point) = self.closest_points(other);
+        point.distance(other_point)
+    }
+
+    /// A point on each centerline where they come closest, the same point when they cross or
+    /// touch
+    pub fn closest_points(&self, other: &Centerline) -> (Vec2, Vec2) {
+        if let (Some(point), _) = self.intersections(other) {
+            return (point, point);
+        }
+
+        let mut candidates = vec![
+            (self.closest_point(other.start()), other.start()),
+            (self.closest_point(other.end()), other.end()),
+            (self.start(), other.closest_point(self.start())),
+            (self.end(), other.closest_point(self.end())),
+        ];
+
+        match (*self, *other) {
+            (Centerline::Segment { start, end }, Centerline::Arc { center, radius, .. })
+            | (Centerline::Arc { center, radius, .. }, Centerline::Segment { start, end }) => {
+                let arc = match self {
+                    Centerline::Arc { .. } => self,
+                    Centerline::Segment { .. } => other,
+                };
+
+                // The closest point on the arc lies on the perpendicular from the center
+                let foot = closest_point_on_segment(center, start, end);
+                let foot_distance = center.distance(foot);
+                if foot_distance > EPSILON {
+                    let on_circle = center + (foot - center) / foot_distance * radius;
+                    if arc.arc_angle(on_circle).is_some() {
+                        candidates.push(match self {
+                            Centerline::Arc { .. } => (on_circle, foot),
+                            Centerline::Segment { .. } => (foot, on_circle),
+                        });
+                    }
+                }
+            }
+            (
+                Centerline::Arc {
+                    center: c1,
+                    radius: r1,
+                    ..
+                },
+                Centerline::Arc {
+                    center: c2,
+                    radius: r2,
+                    ..
+                },
+            ) => {
+                // Closest points of two circles lie on the line through both centers
+                if c1.distance(c2) > EPSILON {
+                    let dir = (c2 - c1).normalize();
+                    for p1 in [c1 + dir * r1, c1 - dir * r1] {
+                        for p2 in [c2 + dir * r2, c2 - dir * r2] {
+                            if self.arc_angle(p1).is_some() && other.arc_angle(p2).is_some() {
+                                candidates.push((p1, p2));
+                            }
+                        }
+                    }
+                }
+            }
+            (Centerline::Segment { .. }, Centerline::Segment { .. }) => {}
+        }
+
+        candidates
+            .into_iter()
+            .min_by(|(a1, a2), (b1, b2)| a1.distance(*a2).total_cmp(&b1.distance(*b2)))
+            .unwrap()
+    }
+
+    /// Angle between the start of the arc and `point`, if `point` falls within the arc's sweep
+    fn arc_angle(&self, point: Vec2) -> Option<f32> {
+        let Centerline::Arc {
+            center,
+            radius,
+            start,
+            sweep,
+        } = *self
+        else {
+            return None;
+        };
+
+        let angle = (start - center).angle_between(point - center);
+        let angle = match sweep.is_sign_negative() {
+            false => angle.rem_euclid(TAU),
+            true => (-angle).rem_euclid(TAU),
+        };
+
+        let tolerance = EPSILON / radius.max(EPSILON);
+        if angle <= sweep.abs() + tolerance {
+            return Some(angle.min(sweep.abs()));
+        }
+
+        // Points just before the start wrap around to a full turn
+        if angle >= TAU - tolerance {
+            return Some(0.0);
+        }
 
-    if denominator.abs() < 1e-6 {
-        return None; // Lines are parallel, no intersection
+        None
     }
+}
 
-    let t = (r2.start() - r1.start()).perp_dot(r2.tangent()) / denominator;
+fn closest_point_on_segment(point: Vec2, start: Vec2, end: Vec2) -> Vec2 {
+    let line = end - start;
+    let length_squared = line.length_squared();
+    if length_squared < EPSILON * EPSILON {
+        return start;
+    }
 
-    Some(r1.start() + t * r1.tangent())
+    let t = (point - start).dot(line) / length_squared;
+    start + line * t.clamp(0.0, 1.0)
 }
 
 #[inline]
-fn point_to_line_distance(line: &LineEdge, point: Vec2) -> f32 {
-    line.tangent().perp_dot(point - line.start()).abs()
+fn point_segment_distance(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    point.distance(closest_point_on_segment(point, start, end))
+}
+
+fn segment_segment_intersections(
+    a0: Vec2,
+    a1: Vec2,
+    b0: Vec2,
+    b1: Vec2,
+) -> (Option<Vec2>, Option<Vec2>) {
+    let a = a1 - a0;
+    let b = b1 - b0;
+    let a_length = a.length();
+    let b_length = b.length();
+
+    // Degenerate segments are points
+    if a_length < EPSILON {
+        return match point_segment_distance(a0, b0, b1) < EPSILON {
+            true => (Some(a0), None),
+            false => (None, None),
+        };
+    }
+    if b_length < EPSILON {
+        return match point_segment_distance(b0, a0, a1) < EPSILON {
+            true => (Some(b0), None),
+            false => (None, None),
+        };
+    }
+
+    let denominator = a.perp_dot(b);
+
+    // Parallel lines only share points when they are collinear
+    if denominator.abs() <= EPSILON * a_length * b_length {
+        let a_dir = a / a_length;
+        if a_dir.perp_dot(b0 - a0).abs() > EPSILON {
+            return (None, None);
+        }
+
+        let t0 = a_dir.dot(b0 - a0);
+        let t1 = a_dir.dot(b1 - a0);
+        let start = t0.min(t1).max(0.0);
+        let end = t0.max(t1).min(a_length);
+
+        if start > end + EPSILON {
+            return (None, None);
+        }
+
+        let p0 = a0 + a_dir * start;
+        return match end - start > EPSILON {
+            true => (Some(p0), Some(a0 + a_dir * end)),
+            false => (Some(p0), None),
+        };
+    }
+
+    let t = (b0 - a0).perp_dot(b) / denominator;
+    let u = (b0 - a0).perp_dot(a) / denominator;
+
+    let a_tolerance = EPSILON / a_length;
+    let b_tolerance = EPSILON / b_length;
+    if t < -a_tolerance || t > 1.0 + a_tolerance || u < -b_tolerance || u > 1.0 + b_tolerance {
+        return (None, None);
+    }
+
+    (Some(a0 + a * t.clamp(0.0, 1.0)), None)
 }
 
-fn is_point_on_ray(ray: &LineEdge, point: Vec2) -> bool {
-    let t = ray.tangent().dot(point - ray.start());
+fn circle_segment_intersections(
+    center: Vec2,
+    radius: f32,
+    start: Vec2,
+    end: Vec2,
+) -> (Option<Vec2>, Option<Vec2>) {
+    let line = end - start;
+    let length = line.length();
+    if length < EPSILON {
+        return match (start.distance(center) - radius).abs() < EPSILON {
+            true => (Some(start), None),
+            false => (None, None),
+        };
+    }
+
+    let dir = line / length;
+    let foot_t = dir.dot(center - start);
+    let foot = start + dir * foot_t;
+    let dist = center.distance(foot);
+
+    if dist > radius + EPSILON {
+        return (None, None);
+    }
+
+    let on_segment = |t: f32| match t >= -EPSILON && t <= length + EPSILON {
+        true => Some(start + dir * t.clamp(0.0, length)),
+        false => None,
+    };
+
+    let half_chord = (radius.powi(2) - dist.powi(2)).max(0.0).sqrt();
+
+    // Tangent line touches the circle in a single point
+    if half_chord < EPSILON {
+        return (on_segment(foot_t), None);
+    }
 
-    t >= 0.0 && t <= ray.length()
+    match (
+        on_segment(foot_t - half_chord),
+        on_segment(foot_t + half_chord),
+    ) {
+        (None, second) => (second, None),
+        points => points,
+    }
 }
 
 fn circle_circle_intersections(
@@ -187,25 +456,26 @@ fn circle_circle_intersections(
     let dir = c2_center - c1_center;
     let dist = dir.length();
 
-    if dist > c1_radius + c2_radius {
+    if dist > c1_radius + c2_radius + EPSILON {
         // No solutions, the circles are separate
         return None;
     }
 
-    if dist < (c1_radius - c2_radius).abs() {
+    if dist < (c1_radius - c2_radius).abs() - EPSILON {
         // No solutions because one circle is contained within the other
         return None;
     }
 
-    if dist == 0.0 && c1_radius == c2_radius {
-        // Circles are coincident and there are an infinite number of solutions
+    if dist < EPSILON {
+        // Circles are concentric, either coincident with an infinite number of solutions or none
         return None;
     }
 
     let dir_n = dir / dist;
 
     let center_chord = (c1_radius.powi(2) - c2_radius.powi(2) + dist.powi(2)) / (2.0 * dist);
-    let half_length = (c1_radius.powi(2) - center_chord.powi(2)).sqrt();
+    // Clamp so touching circles give a single (double) solution instead of NaN
+    let half_length = (c1_radius.powi(2) - center_chord.powi(2)).max(0.0).sqrt();
     let mid = c1_center + center_chord * dir_n;
 
     let half_chord = half_length * dir_n.perp();
@@ -214,3 +484,177 @@ fn circle_circle_intersections(
 
     Some((s1, s2))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Option<Vec2>, b: Vec2) -> bool {
+        a.is_some_and(|a| a.distance(b) < 1e-3)
+    }
+
+    #[test]
+    fn crossing_segments_meet_once() {
+        let (i1, i2) = segment_segment_intersections(
+            Vec2::new(-1.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, -1.0),
+            Vec2::new(0.0, 1.0),
+        );
+        assert!(close(i1, Vec2::ZERO));
+        assert_eq!(i2, None);
+    }
+
+    #[test]
+    fn segments_touching_at_their_ends_meet_there() {
+        let (i1, i2) =
+            segment_segment_intersections(Vec2::ZERO, Vec2::X, Vec2::X, Vec2::new(1.0, 1.0));
+        assert!(close(i1, Vec2::X));
+        assert_eq!(i2, None);
+
+        // An end resting on the middle of the other segment
+        let (i1, _) = segment_segment_intersections(
+            Vec2::new(-1.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::ZERO,
+            Vec2::Y,
+        );
+        assert!(close(i1, Vec2::ZERO));
+    }
+
+    #[test]
+    fn separate_segments_do_not_meet() {
+        let (i1, i2) = segment_segment_intersections(
+            Vec2::ZERO,
+            Vec2::X,
+            Vec2::new(2.0, -1.0),
+            Vec2::new(2.0, 1.0),
+        );
+        assert_eq!((i1, i2), (None, None));
+
+        // Parallel but not collinear
+        let (i1, i2) =
+            segment_segment_intersections(Vec2::ZERO, Vec2::X, Vec2::Y, Vec2::new(1.0, 1.0));
+        assert_eq!((i1, i2), (None, None));
+    }
+
+    #[test]
+    fn collinear_segments_meet_over_their_overlap() {
+        let (i1, i2) = segment_segment_intersections(
+            Vec2::ZERO,
+            Vec2::new(2.0, 0.0),
+            Vec2::new(3.0, 0.0),
+            Vec2::new(1.0, 0.0),
+        );
+        assert!(close(i1, Vec2::X));
+        assert!(close(i2, Vec2::new(2.0, 0.0)));
+
+        // Collinear segments that only share an end point
+        let (i1, i2) =
+            segment_segment_intersections(Vec2::ZERO, Vec2::X, Vec2::X, Vec2::new(2.0, 0.0));
+        assert!(close(i1, Vec2::X));
+        assert_eq!(i2, None);
+
+        // Collinear segments with a gap
+        let (i1, i2) = segment_segment_intersections(
+            Vec2::ZERO,
+            Vec2::X,
+            Vec2::new(2.0, 0.0),
+            Vec2::new(3.0, 0.0),
+        );
+        assert_eq!((i1, i2), (None, None));
+    }
+
+    #[test]
+    fn secant_crosses_circle_twice() {
+        let (i1, i2) = circle_segment_intersections(
+            Vec2::ZERO,
+            1.0,
+            Vec2::new(-2.0, 0.0),
+            Vec2::new(2.0, 0.0),
+        );
+        assert!(close(i1, Vec2::new(-1.0, 0.0)));
+        assert!(close(i2, Vec2::X));
+    }
+
+    #[test]
+    fn tangent_touches_circle_once() {
+        let (i1, i2) = circle_segment_intersections(
+            Vec2::ZERO,
+            1.0,
+            Vec2::new(-2.0, 1.0),
+            Vec2::new(2.0, 1.0),
+        );
+        assert!(close(i1, Vec2::Y));
+        assert_eq!(i2, None);
+
+        let (i1, i2) = circle_segment_intersections(
+            Vec2::ZERO,
+            1.0,
+            Vec2::new(-2.0, 1.1),
+            Vec2::new(2.0, 1.1),
+        );
+        assert_eq!((i1, i2), (None, None));
+    }
+
+    #[test]
+    fn segment_ending_on_circle_touches_it() {
+        let (i1, i2) = circle_segment_intersections(Vec2::ZERO, 1.0, Vec2::X, Vec2::new(2.0, 0.0));
+        assert!(close(i1, Vec2::X));
+        assert_eq!(i2, None);
+
+        // Entirely inside the circle
+        let (i1, i2) = circle_segment_intersections(Vec2::ZERO, 2.0, Vec2::new(-1.0, 0.0), Vec2::X);
+        assert_eq!((i1, i2), (None, None));
+    }
+
+    #[test]
+    fn tangent_arcs_are_zero_apart() {
+        let first = Centerline::Arc {
+            center: Vec2::ZERO,
+            radius: 1.0,
+            start: Vec2::new(0.0, -1.0),
+            sweep: std::f32::consts::PI,
+        };
+        let second = Centerline::Arc {
+            center: Vec2::new(2.0, 0.0),
+            radius: 1.0,
+            start: Vec2::new(2.0, 1.0),
+            sweep: std::f32::consts::PI,
+        };
+        assert!(first.distance(&second) < 1e-3);
+
+        let line = Centerline::Segment {
+            start: Vec2::new(1.0, -1.0),
+            end: Vec2::new(1.0, 1.0),
+        };
+        assert!(first.distance(&line) < 1e-3);
+        assert!(second.distance(&line) < 1e-3);
+    }
+
+    #[test]
+    fn closest_points_lie_on_each_centerline() {
+        let segment = Centerline::Segment {
+            start: Vec2::new(0.0, 0.0),
+            end: Vec2::new(4.0, 0.0),
+        };
+        let below = Centerline::Segment {
+            start: Vec2::new(3.0, -1.0),
+            end: Vec2::new(3.0, -3.0),
+        };
+        let (point, other_point) = segment.closest_points(&below);
+        assert!(point.distance(Vec2::new(3.0, 0.0)) < 1e-4);
+        assert!(other_point.distance(Vec2::new(3.0, -1.0)) < 1e-4);
+
+        let arc = Centerline::Arc {
+            center: Vec2::new(1.0, 3.0),
+            radius: 1.0,
+            start: Vec2::new(0.0, 3.0),
+            sweep: std::f32::consts::PI,
+        };
+        let (point, other_point) = arc.closest_points(&segment);
+        assert!(point.distance(Vec2::new(1.0, 2.0)) < 1e-4);
+        assert!(other_point.distance(Vec2::new(1.0, 0.0)) < 1e-4);
+        assert!((arc.distance(&segment) - 2.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,368 @@
+use std::ops::Range;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// What a strip of road across its width is for. Only driving lanes carry vehicles; lane
+/// numbers count the driving lanes along the edge from the side of the opposing traffic, as
+/// `DriveSide` decides.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LaneKind {
+    #[default]
+    Driving,
+    Bike,
+    Sidewalk,
+    Parking,
+    Median,
+    Shoulder,
+}
+
+impl LaneKind {
+    /// Index the world shader knows the kind by
+    pub fn index(self) -> u32 {
+        self as u32
+    }
+}
+
+/// Way vehicles drive on a lane, relative to the edge it is part of
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LaneDirection {
+    #[default]
+    Forward,
+    Backward,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LaneProfile {
+    pub kind: LaneKind,
+    /// Width at the start of the edge
+    pub width: f32,
+    pub direction: LaneDirection,
+    /// Width at the end of the edge, for lanes that are added or dropped along it. The width
+    /// changes evenly in between.
+    #[serde(default)]
+    pub end_width: Option<f32>,
+}
+
+impl LaneProfile {
+    /// Lane driven along the edge
+    pub fn new(kind: LaneKind, width: f32) -> Self {
+        Self {
+            kind,
+            width,
+            direction: LaneDirection::Forward,
+            end_width: None,
+        }
+    }
+
+    /// The same lane, reaching `end_width` at the end of the edge
+    pub fn tapered(self, end_width: f32) -> Self {
+        Self {
+            end_width: (end_width != self.width).then_some(end_width),
+            ..self
+        }
+    }
+
+    /// Width at `along`, the fraction of the edge's length from its start
+    pub fn width_at(&self, along: f32) -> f32 {
+        let end_width = self.end_width.unwrap_or(self.width);
+        self.width + (end_width - self.width) * along
+    }
+
+    /// Stretch of the edge, in fractions of its length, where the lane is at least half as
+    /// wide as it gets. Vehicles only move onto the lane there, and have to leave it before
+    /// it narrows further.
+    pub fn open(&self) -> Range<f32> {
+        let end_width = self.end_width.unwrap_or(self.width);
+        let half = self.width.max(end_width) * 0.5;
+        let change = end_width - self.width;
+
+        if change > 0.0 {
+            ((half - self.width) / change).max(0.0)..1.0
+        } else if change < 0.0 {
+            0.0..((half - self.width) / change).min(1.0)
+        } else {
+            0.0..1.0
+        }
+    }
+
+    /// The same lane, driven against the edge
+    pub fn backward(self) -> Self {
+        Self {
+            direction: LaneDirection::Backward,
+            ..self
+        }
+    }
+
+    /// Whether vehicles of the edge drive on the lane
+    pub fn is_driven(&self) -> bool {
+        self.kind == LaneKind::Driving && self.direction == LaneDirection::Forward
+    }
+
+    /// Whether vehicles of the edge going the other way drive on the lane
+    pub fn is_opposing(&self) -> bool {
+        self.kind == LaneKind::Driving && self.direction == LaneDirection::Backward
+    }
+}
+
+/// Side of the road traffic keeps to, for the whole network. Lane 0 of an edge is the driving
+/// lane next to the opposing traffic and higher lanes lie towards the kerb, so lane 0 is the
+/// leftmost lane with right-hand traffic and the rightmost with left-hand traffic.
+#[derive(Resource, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub enum DriveSide {
+    #[default]
+    Right,
+    Left,
+}
+
+impl DriveSide {
+    /// Lane `lane` out of `lanes` driving lanes counted from the left instead, as
+    /// `CrossSection::offset` takes it. Counting from the left back to lane numbers is the
+    /// same mapping.
+    pub fn from_left(self, lane: f32, lanes: u8) -> f32 {
+        match self {
+            Self::Right => lane,
+            Self::Left => lanes as f32 - 1.0 - lane,
+        }
+    }
+
+    /// Turns angles to the left into angles towards the opposing traffic when multiplied
+    pub fn inner_sign(self) -> f32 {
+        match self {
+            Self::Right => 1.0,
+            Self::Left => -1.0,
+        }
+    }
+
+    pub fn other(self) -> Self {
+        match self {
+            Self::Right => Self::Left,
+            Self::Left => Self::Right,
+        }
+    }
+}
+
+/// Strips of an edge from its left side to its right, looking along the edge. Two-way roads
+/// have the lanes of both directions in the cross-section of each of their edges, and each
+/// edge only carries the vehicles of its forward lanes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrossSection {
+    pub lanes: Vec<LaneProfile>,
+}
+
+impl CrossSection {
+    /// Just `lanes` driving lanes of the same width
+    pub fn uniform(lanes: u8, width: f32) -> Self {
+        Self {
+            lanes: vec![LaneProfile::new(LaneKind::Driving, width); lanes as usize],
+        }
+    }
+
+    /// The cross-section as seen from the other end, where backward lanes are forward lanes
+    pub fn reversed(&self) -> Self {
+        Self {
+            lanes: self
+                .lanes
+                .iter()
+                .rev()
+                .map(|lane| {
+                    LaneProfile {
+                        direction: match lane.direction {
+                            LaneDirection::Forward => LaneDirection::Backward,
+                            LaneDirection::Backward => LaneDirection::Forward,
+                        },
+                        width: lane.width_at(1.0),
+                        ..*lane
+                    }
+                    .tapered(lane.width)
+                })
+                .collect(),
+        }
+    }
+
+    /// The cross-section flipped from left to right, with every lane still driven the same
+    /// way. Turns a road laid out for one `DriveSide` into the same road for the other.
+    pub fn mirrored(&self) -> Self {
+        Self {
+            lanes: self.lanes.iter().rev().copied().collect(),
+        }
+    }
+
+    /// The stretch of the cross-section from `from` to `to`, in fractions of the edge's
+    /// length, for the pieces of a split edge
+    pub fn between(&self, from: f32, to: f32) -> Self {
+        Self {
+            lanes: self
+                .lanes
+                .iter()
+                .map(|lane| {
+                    LaneProfile {
+                        width: lane.width_at(from),
+                        ..*lane
+                    }
+                    .tapered(lane.width_at(to))
+                })
+                .collect(),
+        }
+    }
+
+    /// The lanes as they are at `along`, the fraction of the edge's length from its start,
+    /// without the ones that have no width there
+    pub fn at(&self, along: f32) -> Self {
+        Self {
+            lanes: self
+                .between(along, along)
+                .lanes
+                .into_iter()
+                .filter(|lane| lane.width > 0.0)
+                .collect(),
+        }
+    }
+
+    /// Cross-section that turns the end of this one into the start of `to` along an edge.
+    /// The lanes of both are matched from the middle of the road outwards, and lanes only one
+    /// of them has are added or dropped, so extra lanes of a carriageway drop out at the kerb.
+    pub fn taper_to(&self, to: &CrossSection, side: DriveSide) -> Self {
+        let (from, to) = (self.at(1.0), to.at(0.0));
+        let (from_left, from_right) = from.split(side);
+        let (to_left, to_right) = to.split(side);
+
+        let mut left = taper_lanes(from_left.iter().rev(), to_left.iter().rev());
+        left.reverse();
+        let right = taper_lanes(from_right.iter(), to_right.iter());
+
+        Self {
+            lanes: [left, right].concat(),
+        }
+    }
+
+    /// Lanes left and right of the middle of the road, between the lanes going each way. One-way
+    /// roads have all their lanes on the side traffic keeps to.
+    fn split(&self, side: DriveSide) -> (&[LaneProfile], &[LaneProfile]) {
+        let middle = match side {
+            DriveSide::Right => self
+                .lanes
+                .iter()
+                .rposition(LaneProfile::is_opposing)
+                .map_or(0, |index| index + 1),
+            DriveSide::Left => self
+                .lanes
+                .iter()
+                .position(LaneProfile::is_opposing)
+                .unwrap_or(self.lanes.len()),
+        };
+
+        self.lanes.split_at(middle)
+    }
+
+    /// Whether any lane is added or dropped along the edge
+    pub fn is_tapered(&self) -> bool {
+        self.lanes.iter().any(|lane| lane.end_width.is_some())
+    }
+
+    /// Width where the cross-section is widest, which is at one of the ends of the edge
+    pub fn width(&self) -> f32 {
+        self.width_at(0.0).max(self.width_at(1.0))
+    }
+
+    /// Width at `along`, the fraction of the edge's length from its start
+    pub fn width_at(&self, along: f32) -> f32 {
+        self.lanes.iter().map(|lane| lane.width_at(along)).sum()
+    }
+
+    /// Number of lanes vehicles drive on along the edge, with the ones added or dropped along it
+    pub fn driving_lanes(&self) -> u8 {
+        self.driven().count() as u8
+    }
+
+    /// Number of lanes vehicles drive on against the edge
+    pub fn backward_lanes(&self) -> u8 {
+        self.lanes.iter().filter(|lane| lane.is_opposing()).count() as u8
+    }
+
+    /// Lanes driven along the edge, from the left
+    pub fn driven(&self) -> impl Iterator<Item = &LaneProfile> {
+        self.lanes.iter().filter(|lane| lane.is_driven())
+    }
+
+    /// Distance of the middle of each lane driven along the edge to the left of the
+    /// centerline, and its width, at `along`, the fraction of the edge's length from its start
+    pub fn driving(&self, along: f32) -> impl Iterator<Item = (f32, f32)> + '_ {
+        let mut left = self.width_at(along) * 0.5;
+        self.lanes.iter().filter_map(move |lane| {
+            let width = lane.width_at(along);
+            let middle = left - width * 0.5;
+            left -= width;
+            lane.is_driven().then_some((middle, width))
+        })
+    }
+
+    /// Distance to the left of the centerline of driving lane `lane` at `along`, the fraction
+    /// of the edge's length from its start. The lane may lie between two lanes. Lanes before
+    /// the first and after the last continue outside the cross-section with the width of the
+    /// outer driving lanes.
+    pub fn offset(&self, lane: f32, along: f32) -> f32 {
+        let driving = self.driving(along).collect::<Vec<(f32, f32)>>();
+        let (Some((first, first_width)), Some((last, last_width))) =
+            (driving.first(), driving.last())
+        else {
+            return 0.0;
+        };
+
+        let half_width = self.width_at(along) * 0.5;
+        let count = driving.len() as f32;
+        if lane < 0.0 {
+            return (half_width + (-lane - 0.5) * first_width).max(*first);
+        }
+        if lane > count - 1.0 {
+            return (-half_width - (lane - count + 0.5) * last_width).min(*last);
+        }
+
+        let below = lane.floor() as usize;
+        let above = lane.ceil() as usize;
+        let fraction = lane - below as f32;
+        driving[below].0 * (1.0 - fraction) + driving[above].0 * fraction
+    }
+}
+
+/// Tapers the lanes on one side of the road from `from` to `to`, both listed from the middle of
+/// the road outwards. Lanes of the same kind going the same way are matched in order, and
+/// where they differ, the lane that has more of its kind still to come on its side is dropped
+/// or added.
+fn taper_lanes<'a>(
+    from: impl Iterator<Item = &'a LaneProfile>,
+    to: impl Iterator<Item = &'a LaneProfile>,
+) -> Vec<LaneProfile> {
+    let from = from.collect::<Vec<&LaneProfile>>();
+    let to = to.collect::<Vec<&LaneProfile>>();
+    let same = |a: &LaneProfile, b: &LaneProfile| a.kind == b.kind && a.direction == b.direction;
+    let remaining = |lanes: &[&LaneProfile], lane: &LaneProfile| {
+        lanes.iter().filter(|other| same(other, lane)).count()
+    };
+
+    let mut lanes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    loop {
+        match (from.get(i), to.get(j)) {
+            (Some(a), Some(b)) if same(a, b) => {
+                lanes.push(a.tapered(b.width));
+                i += 1;
+                j += 1;
+            }
+            (Some(a), Some(_)) if remaining(&from[i..], a) > remaining(&to[j..], a) => {
+                lanes.push(a.tapered(0.0));
+                i += 1;
+            }
+            (Some(_), Some(b)) | (None, Some(b)) => {
+                lanes.push(LaneProfile { width: 0.0, ..**b }.tapered(b.width));
+                j += 1;
+            }
+            (Some(a), None) => {
+                lanes.push(a.tapered(0.0));
+                i += 1;
+            }
+            (None, None) => return lanes,
+        }
+    }
+}
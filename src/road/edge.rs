@@ -1,13 +1,17 @@
-use std::f32::consts::{PI, TAU};
+use std::{
+    f32::consts::{PI, TAU},
+    ops::Range,
+};
 
 use bevy::{
     math::bounding::{Aabb3d, IntersectsVolume},
     prelude::*,
 };
+use serde::{Deserialize, Serialize};
 
-use super::ROAD_WIDTH;
+use super::{collision::Centerline, cross_section::DriveSide, palette::RoadType};
 
-#[derive(Debug, Default, Reflect, Clone, Copy, PartialEq)]
+#[derive(Debug, Default, Reflect, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Twist {
     #[default]
     CounterClockwise,
@@ -15,7 +19,16 @@ pub enum Twist {
     Straight,
 }
 
-#[derive(Component, Debug)]
+/// Where two edges cross and how far their road surfaces overlap
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeIntersection {
+    /// Points where the centerlines cross, or the ends of the shared stretch when they overlap
+    pub points: Vec<Vec3>,
+    /// Depth with which the road surfaces overlap, measured across the roads
+    pub overlap: f32,
+}
+
+#[derive(Component, Debug, Clone)]
 pub struct RoadEdge {
     start: Transform,
     end: Transform,
@@ -23,7 +36,7 @@ pub struct RoadEdge {
     radius: f32,
     length: f32,
     twist: Twist,
-    lanes: u8,
+    road_type: RoadType,
     aabb3: Aabb3d,
 }
 
@@ -35,9 +48,17 @@ impl RoadEdge {
         radius: f32,
         length: f32,
         twist: Twist,
-        lanes: u8,
-        aabb3: Aabb3d,
+        road_type: RoadType,
     ) -> Self {
+        let aabb3 = compute_aabb3(
+            start.translation.xz(),
+            end.translation.xz(),
+            center.xz(),
+            radius,
+            twist,
+            road_type.width(),
+        );
+
         Self {
             start,
             end,
@@ -45,12 +66,12 @@ impl RoadEdge {
             length,
             radius,
             twist,
-            lanes,
+            road_type,
             aabb3,
         }
     }
 
-    pub fn from_start_end(start: Transform, end: Vec3, lanes: u8) -> Self {
+    pub fn from_start_end(start: Transform, end: Vec3, road_type: RoadType) -> Self {
         let startpoint = start.translation.xz();
         let endpoint = end.xz();
         let chord = endpoint - startpoint;
@@ -70,7 +91,7 @@ impl RoadEdge {
                 center.xz(),
                 0.0,
                 Twist::Straight,
-                lanes,
+                road_type.width(),
             );
 
             return Self {
@@ -80,7 +101,7 @@ impl RoadEdge {
                 radius: 0.0,
                 length,
                 twist: Twist::Straight,
-                lanes,
+                road_type,
                 aabb3,
             };
         }
@@ -109,7 +130,8 @@ impl RoadEdge {
             Twist::Straight => unreachable!(),
         };
 
-        let aabb3 = compute_aabb3(startpoint, endpoint, center, radius, twist, lanes);
+        let aabb3 =
+            compute_aabb3(startpoint, endpoint, center, radius, twist, road_type.width());
         let center = center.extend(0.0).xzy();
         let end = Transform::from_translation(end).looking_to(end_direction, Vec3::Y);
 
@@ -120,16 +142,17 @@ impl RoadEdge {
             radius,
             length,
             twist,
-            lanes,
+            road_type,
             aabb3,
         }
     }
 
-    pub fn get_end_transform(&self, lane: Option<u8>) -> Transform {
+    /// End of the edge, or of lane `lane` counted as `side` decides
+    pub fn get_end_transform(&self, lane: Option<u8>, side: DriveSide) -> Transform {
         match lane {
             Some(l) => {
-                let max = (self.lanes - 1) as f32 * 0.5 * ROAD_WIDTH;
-                let offset = max - l as f32 * ROAD_WIDTH;
+                let lane = side.from_left(l as f32, self.lanes());
+                let offset = self.road_type.cross_section.offset(lane, 1.0);
                 let translation = self.end.translation + *self.end.left() * offset;
 
                 self.end.with_translation(translation)
@@ -138,6 +161,56 @@ impl RoadEdge {
         }
     }
 
+    /// Splits the edge in two at `length` along the edge. Lanes added or dropped along the
+    /// edge are as wide at the cut on both pieces.
+    pub fn split(&self, length: f32) -> (RoadEdge, RoadEdge) {
+        let along = self.along(length);
+        let first = RoadEdge::from_start_end(
+            self.start,
+            self.interpolate(length).translation,
+            self.road_type.between(0.0, along),
+        );
+        let second = RoadEdge::from_start_end(
+            first.end(),
+            self.end.translation,
+            self.road_type.between(along, 1.0),
+        );
+
+        (first, second)
+    }
+
+    /// The same stretch of road, driven from its end to its start
+    pub fn reversed(&self) -> RoadEdge {
+        let turn = |transform: Transform| transform.looking_to(-*transform.forward(), Vec3::Y);
+        let twist = match self.twist {
+            Twist::CounterClockwise => Twist::Clockwise,
+            Twist::Clockwise => Twist::CounterClockwise,
+            Twist::Straight => Twist::Straight,
+        };
+
+        RoadEdge::new(
+            turn(self.end),
+            turn(self.start),
+            self.center,
+            self.radius,
+            self.length,
+            twist,
+            self.road_type.reversed(),
+        )
+    }
+
+    /// Whether `other` runs back along the same centerline, like the two edges of a two-way road
+    pub fn is_reverse_of(&self, other: &RoadEdge) -> bool {
+        let close = |a: Transform, b: Transform| a.translation.distance(b.translation) < 1e-3;
+
+        close(self.start, other.end)
+            && close(self.end, other.start)
+            && close(
+                self.interpolate(self.length * 0.5),
+                other.interpolate(other.length * 0.5),
+            )
+    }
+
     pub fn resize(&mut self, length: f32) {
         let new_end = self.interpolate(length);
         self.length = length;
@@ -157,7 +230,7 @@ impl RoadEdge {
 
     pub fn coord_to_length(&self, coord: Vec3) -> f32 {
         match self.twist {
-            Twist::Straight => coord
+            Twist::Straight => (coord - self.start.translation)
                 .project_onto(self.end.translation - self.start.translation)
                 .length(),
             _ => self.radius * self.coord_to_angle(coord),
@@ -181,9 +254,17 @@ impl RoadEdge {
         rotated
     }
 
-    pub fn interpolate_lane(&self, length: f32, lane: i32) -> Transform {
-        let max = (self.lanes - 1) as f32 * 0.5 * ROAD_WIDTH;
-        let offset = max - lane as f32 * ROAD_WIDTH;
+    /// Point `length` along lane `lane`, counted from the opposing traffic of `side` towards
+    /// the kerb
+    pub fn interpolate_lane(&self, length: f32, lane: i32, side: DriveSide) -> Transform {
+        self.interpolate_lateral(length, lane as f32, side)
+    }
+
+    /// Like `interpolate_lane`, but `lane` may lie between two lanes, for instance while a
+    /// vehicle is changing lanes
+    pub fn interpolate_lateral(&self, length: f32, lane: f32, side: DriveSide) -> Transform {
+        let lane = side.from_left(lane, self.lanes());
+        let offset = self.road_type.cross_section.offset(lane, self.along(length));
 
         match self.twist {
             Twist::Straight => {
@@ -205,7 +286,7 @@ impl RoadEdge {
                 interp
             }
             Twist::CounterClockwise => {
-                let offset = self.start.right() * offset;
+                let offset = self.start.left() * offset;
                 let rotation = Quat::from_axis_angle(Vec3::Y, -length / self.radius);
 
                 let mut interp = self.start.clone();
@@ -217,29 +298,67 @@ impl RoadEdge {
         }
     }
 
+    /// Stretch of lane `lane`, counted as `side` decides, where vehicles may drive on it.
+    /// Lanes dropped along the edge end before it does, and added lanes start after it does.
+    pub fn lane_open(&self, lane: u8, side: DriveSide) -> Range<f32> {
+        let index = side.from_left(lane as f32, self.lanes()) as usize;
+        let open = self
+            .road_type
+            .cross_section
+            .driven()
+            .nth(index)
+            .map_or(0.0..1.0, |lane| lane.open());
+
+        open.start * self.length..open.end * self.length
+    }
+
+    /// Lanes open at `length` along the edge, counted as `side` decides. Lanes are added and
+    /// dropped at the kerb, so these are next to each other.
+    pub fn lanes_open_at(&self, length: f32, side: DriveSide) -> Range<u8> {
+        let mut open = (0..self.lanes()).filter(|lane| {
+            let open = self.lane_open(*lane, side);
+            open.start <= length && length <= open.end
+        });
+        let Some(first) = open.next() else {
+            return 0..0;
+        };
+
+        first..open.next_back().unwrap_or(first) + 1
+    }
+
+    /// Fraction of the edge's length that `length` is
+    fn along(&self, length: f32) -> f32 {
+        match self.length > 0.0 {
+            true => (length / self.length).clamp(0.0, 1.0),
+            false => 0.0,
+        }
+    }
+
     pub fn intersects_point(&self, hitpoint: Vec3) -> bool {
-        let road_thickness = self.lanes as f32 * ROAD_WIDTH * 0.5;
+        let width_at = |length: f32| self.road_type.cross_section.width_at(self.along(length));
 
         match self.twist {
             Twist::Straight => {
                 let line = (self.end.translation - self.start.translation).normalize();
 
-                let projection_length = hitpoint.dot(line);
+                let projection_length = (hitpoint - self.start.translation).dot(line);
                 if projection_length < 0.0 || projection_length > self.length {
                     return false;
                 }
 
-                let closest_point_on_line = projection_length * line;
+                let closest_point_on_line = self.start.translation + projection_length * line;
                 let vector_to_line = closest_point_on_line - hitpoint;
                 let distance = vector_to_line.length();
 
-                distance <= road_thickness
+                distance <= width_at(projection_length) * 0.5
             }
             _ => {
-                if self.coord_to_length(hitpoint) > self.length {
+                let length = self.coord_to_length(hitpoint);
+                if length > self.length {
                     return false;
                 }
 
+                let road_thickness = width_at(length) * 0.5;
                 let radius = (hitpoint - self.center).length();
                 if radius < self.radius - road_thickness || radius > self.radius + road_thickness {
                     return false;
@@ -250,48 +369,63 @@ impl RoadEdge {
         }
     }
 
-    pub fn intersects_edge(&self, other: &RoadEdge) -> bool {
+    pub fn intersects_edge(&self, other: &RoadEdge) -> Option<EdgeIntersection> {
         if !self.aabb3.intersects(&other.aabb3()) {
-            return false;
+            return None;
         }
 
-        // Arc - Arc
-        if self.twist != Twist::Straight && other.twist() != Twist::Straight {
-            if let Some((i1, i2)) = circle_intersections(
-                self.center.xz(),
-                self.radius,
-                other.center().xz(),
-                other.radius(),
-            ) {
-                if self.intersects_point(i1.extend(0.0).xzy())
-                    && other.intersects_point(i1.extend(0.0).xzy())
-                {
-                    return true;
-                }
+        let centerline = self.centerline();
+        let other_centerline = other.centerline();
 
-                if self.intersects_point(i2.extend(0.0).xzy())
-                    && other.intersects_point(i2.extend(0.0).xzy())
-                {
-                    return true;
-                }
-            }
-
-            return false;
+        // Tapered roads reach as far as they are wide where the two come closest
+        let (point, other_point) = centerline.closest_points(&other_centerline);
+        let along = |centerline: &Centerline, point: Vec2| {
+            centerline.project(point) / centerline.length().max(f32::EPSILON)
+        };
+        let width = self.road_type.cross_section.width_at(along(&centerline, point));
+        let other_width =
+            (other.road_type().cross_section).width_at(along(&other_centerline, other_point));
+        let reach = (width + other_width) * 0.5;
+
+        let distance = point.distance(other_point);
+        if distance > reach {
+            return None;
         }
 
-        // Arc - Straight
-        if self.twist == Twist::Straight && other.twist() != Twist::Straight
-            || self.twist != Twist::Straight && other.twist() == Twist::Straight
-        {
-            return true;
-        }
+        let (i1, i2) = centerline.intersections(&other_centerline);
+        let points = [i1, i2]
+            .into_iter()
+            .flatten()
+            .map(|point| point.extend(0.0).xzy())
+            .collect();
+
+        Some(EdgeIntersection {
+            points,
+            overlap: reach - distance,
+        })
+    }
 
-        // Straight - Straight
-        if self.twist == Twist::Straight && other.twist() == Twist::Straight {
-            return true;
-        }
+    pub fn centerline(&self) -> Centerline {
+        let start = self.start.translation.xz();
 
-        true
+        match self.twist {
+            Twist::Straight => Centerline::Segment {
+                start,
+                end: self.end.translation.xz(),
+            },
+            Twist::CounterClockwise => Centerline::Arc {
+                center: self.center.xz(),
+                radius: self.radius,
+                start,
+                sweep: self.length / self.radius,
+            },
+            Twist::Clockwise => Centerline::Arc {
+                center: self.center.xz(),
+                radius: self.radius,
+                start,
+                sweep: -self.length / self.radius,
+            },
+        }
     }
 
     // Properties
@@ -312,7 +446,11 @@ impl RoadEdge {
     }
 
     pub fn lanes(&self) -> u8 {
-        self.lanes
+        self.road_type.lanes()
+    }
+
+    pub fn road_type(&self) -> &RoadType {
+        &self.road_type
     }
 
     pub fn length(&self) -> f32 {
@@ -329,8 +467,8 @@ impl RoadEdge {
 }
 
 #[rustfmt::skip]
-fn compute_aabb3(start: Vec2, end: Vec2, center: Vec2, radius: f32, twist: Twist, lanes: u8) -> Aabb3d {
-    let half_width = lanes as f32 * ROAD_WIDTH * 0.5;
+fn compute_aabb3(start: Vec2, end: Vec2, center: Vec2, radius: f32, twist: Twist, width: f32) -> Aabb3d {
+    let half_width = width * 0.5;
 
     if Twist::Straight == twist {
         let min_x = start.x.min(end.x);
@@ -381,40 +519,3 @@ fn compute_aabb3(start: Vec2, end: Vec2, center: Vec2, radius: f32, twist: Twist
         max: Vec3::new(max_x, 0.1, max_z),
     }
 }
-
-fn circle_intersections(
-    c1_center: Vec2,
-    c1_radius: f32,
-    c2_center: Vec2,
-    c2_radius: f32,
-) -> Option<(Vec2, Vec2)> {
-    let dir = c2_center - c1_center;
-    let dist = dir.length();
-
-    if dist > c1_radius + c2_radius {
-        // No solutions, the circles are separate
-        return None;
-    }
-
-    if dist < (c1_radius - c2_radius).abs() {
-        // No solutions because one circle is contained within the other
-        return None;
-    }
-
-    if dist == 0.0 && c1_radius == c2_radius {
-        // Circles are coincident and there are an infinite number of solutions
-        return None;
-    }
-
-    let dir_n = dir / dist;
-
-    let center_chord = (c1_radius.powi(2) - c2_radius.powi(2) + dist.powi(2)) / (2.0 * dist);
-    let half_length = (c1_radius.powi(2) - center_chord.powi(2)).sqrt();
-    let mid = c1_center + center_chord * dir_n;
-
-    let half_chord = half_length * dir_n.perp();
-    let s1 = mid - half_chord;
-    let s2 = mid + half_chord;
-
-    Some((s1, s2))
-}
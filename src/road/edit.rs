@@ -0,0 +1,518 @@
+use bevy::{
+    ecs::system::SystemParam,
+    input::{
+        common_conditions::{input_just_pressed, input_just_released},
+        mouse::MouseMotion,
+    },
+    prelude::*,
+    utils::HashMap,
+};
+
+use crate::{raycast::Raycast, states::GameState};
+
+use super::{
+    asset::inherit_network_asset,
+    biarc,
+    edge::RoadEdge,
+    graph::{EdgeNodes, RoadGraph},
+    history::BuildHistory,
+    node::RoadNode,
+    palette::RoadType,
+    placeholder::{spawn_node, BuildSystemSet, RoadPlaceholder},
+    world::WorldTile,
+    RoadSpawner, ROAD_WIDTH,
+};
+
+/// Handles closer than this to the cursor can be grabbed
+const HANDLE_RADIUS: f32 = 0.4 * ROAD_WIDTH;
+
+/// How far the cursor has to move before a grabbed handle changes the road
+const DRAG_THRESHOLD: f32 = 0.05;
+
+/// Edges whose headings differ less than this where they meet run on into each other
+const TANGENT_TOLERANCE: f32 = 1e-3;
+
+pub struct EditPlugin;
+impl Plugin for EditPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HandleDrag>()
+            .add_systems(
+                Update,
+                (
+                    draw_handles,
+                    grab_handle.run_if(input_just_pressed(MouseButton::Left)),
+                    drag_handle.run_if(on_event::<MouseMotion>()),
+                )
+                    .chain()
+                    .in_set(BuildSystemSet::NotBuilding),
+            )
+            // Only after the build systems, so letting go of a handle doesn't start a road too
+            .add_systems(
+                PostUpdate,
+                release_handle.run_if(input_just_released(MouseButton::Left)),
+            )
+            .add_systems(OnExit(GameState::Building), release_handle);
+    }
+}
+
+/// Whether a handle is being dragged
+pub fn handle_grabbed(drag: Res<HandleDrag>) -> bool {
+    drag.grabbed.is_some()
+}
+
+/// Something on an edge that can be dragged around
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Grab {
+    /// Start or end of edges. Moves every edge connected there.
+    Node(Entity),
+    /// Middle of an edge. Bends it into a biarc through the cursor.
+    Midpoint(Entity),
+}
+
+/// The handle being dragged and how the edges around it follow
+#[derive(Resource, Debug, Default)]
+pub struct HandleDrag {
+    grabbed: Option<Grab>,
+    origin: Vec3,
+    /// Only planned once the cursor moves, so clicking a handle doesn't change anything
+    bends: Option<Vec<Bend>>,
+}
+
+/// How an edge follows a dragged handle. Ends that connect to other edges keep their heading, so
+/// the road stays smooth there. An edge that has to keep the heading at both ends becomes a
+/// biarc, with its second arc in `split`.
+#[derive(Debug)]
+struct Bend {
+    edge: Entity,
+    original: RoadEdge,
+    follow: Follow,
+    /// Edge and node added after the edge where it became a biarc
+    split: Option<(Entity, Entity)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Follow {
+    /// Keeps its start and ends at the handle
+    Forward,
+    /// Keeps its end and starts at the handle
+    Backward,
+    /// Starts at the handle with the heading the edge ends with
+    After(Entity),
+    /// Ends at the handle with the heading the edge starts with
+    Before(Entity),
+    /// Runs from its start through the handle to its end
+    Through { start_fixed: bool, end_fixed: bool },
+    /// Runs back along the biarc the edge `first` and the edge split off it, `second`, became
+    Twin { first: Entity, second: Entity },
+}
+
+/// Where the handles are
+#[derive(SystemParam)]
+struct EdgeHandles<'w, 's> {
+    graph: Res<'w, RoadGraph>,
+    edges: Query<'w, 's, (Entity, &'static RoadEdge), Without<RoadPlaceholder>>,
+    nodes: Query<'w, 's, (Entity, &'static Transform), With<RoadNode>>,
+}
+
+impl EdgeHandles<'_, '_> {
+    fn iter(&self) -> impl Iterator<Item = (Grab, Vec3)> + '_ {
+        let nodes = self
+            .nodes
+            .iter()
+            .filter(|(entity, _)| self.graph.degree(*entity) > 0)
+            .map(|(entity, transform)| (Grab::Node(entity), transform.translation));
+        let midpoints = self.edges.iter().map(|(entity, edge)| {
+            let midpoint = edge.interpolate(edge.length() * 0.5).translation;
+            (Grab::Midpoint(entity), midpoint)
+        });
+
+        nodes.chain(midpoints)
+    }
+
+    fn nearest(&self, point: Vec3) -> Option<(Grab, Vec3)> {
+        self.iter()
+            .filter(|(_, position)| position.xz().distance(point.xz()) < HANDLE_RADIUS)
+            .min_by(|(_, a), (_, b)| {
+                let distance = |position: &Vec3| position.xz().distance_squared(point.xz());
+                distance(a).total_cmp(&distance(b))
+            })
+    }
+}
+
+fn draw_handles(
+    world_cast: Raycast<With<WorldTile>>,
+    handles: EdgeHandles,
+    drag: Res<HandleDrag>,
+    mut gizmos: Gizmos,
+) {
+    let hovered = match drag.grabbed {
+        Some(grab) => Some(grab),
+        None => world_cast
+            .cursor_ray()
+            .and_then(|(_, hitpoint)| handles.nearest(hitpoint))
+            .map(|(grab, _)| grab),
+    };
+
+    for (grab, position) in handles.iter() {
+        let color = match grab {
+            _ if Some(grab) == hovered => Color::ORANGE,
+            Grab::Node(_) => Color::WHITE,
+            Grab::Midpoint(_) => Color::YELLOW,
+        };
+
+        gizmos.circle(
+            position + Vec3::Y * 0.01,
+            Direction3d::Y,
+            HANDLE_RADIUS * 0.5,
+            color,
+        );
+    }
+}
+
+fn grab_handle(
+    world_cast: Raycast<With<WorldTile>>,
+    spawner_cast: Raycast<With<RoadSpawner>>,
+    handles: EdgeHandles,
+    mut drag: ResMut<HandleDrag>,
+) {
+    // Clicking a spawner starts a road instead
+    if spawner_cast.cursor_ray().is_some() {
+        return;
+    }
+
+    let Some((_, hitpoint)) = world_cast.cursor_ray() else {
+        return;
+    };
+    let Some((grab, position)) = handles.nearest(hitpoint) else {
+        return;
+    };
+
+    *drag = HandleDrag {
+        grabbed: Some(grab),
+        origin: position,
+        bends: None,
+    };
+}
+
+fn drag_handle(
+    world_cast: Raycast<With<WorldTile>>,
+    mut drag: ResMut<HandleDrag>,
+    graph: Res<RoadGraph>,
+    mut edges: EdgeQuery,
+    mut nodes: Query<&mut Transform, With<RoadNode>>,
+    mut history: ResMut<BuildHistory>,
+    mut commands: Commands,
+) {
+    let Some(grab) = drag.grabbed else {
+        return;
+    };
+    let Some((_, hitpoint)) = world_cast.cursor_ray() else {
+        return;
+    };
+    let target = (hitpoint * 4.0).floor() * 0.25;
+
+    let Some(bends) = &drag.bends else {
+        if drag.origin.xz().distance(target.xz()) < DRAG_THRESHOLD {
+            return;
+        }
+
+        history.record(BuildHistory::snapshot(&graph, |entity| {
+            edges.get(entity).ok().map(|(edge, _)| edge)
+        }));
+
+        // The edges that are split only get their final shape with the next movement
+        let plan = Planner {
+            graph: &graph,
+            edges: &edges,
+        };
+        drag.bends = Some(match grab {
+            Grab::Node(node) => plan.node(node, &mut commands),
+            Grab::Midpoint(edge) => plan.midpoint(edge, &mut commands),
+        });
+
+        return;
+    };
+
+    if let Grab::Node(node) = grab {
+        if let Ok(mut transform) = nodes.get_mut(node) {
+            transform.translation = target;
+        }
+    }
+
+    let mut solved: HashMap<Entity, RoadEdge> = HashMap::new();
+    for bend in bends {
+        let (edge, second) = bend.solve(target, &solved);
+
+        if let (Some((split_edge, split_node)), Some(second)) = (bend.split, second) {
+            if let Ok(mut transform) = nodes.get_mut(split_node) {
+                transform.translation = second.start().translation;
+            }
+            if let Ok((mut split, _)) = edges.get_mut(split_edge) {
+                *split = second.clone();
+            }
+            solved.insert(split_edge, second);
+        }
+
+        if let Ok((mut current, _)) = edges.get_mut(bend.edge) {
+            *current = edge.clone();
+        }
+        solved.insert(bend.edge, edge);
+    }
+}
+
+fn release_handle(mut drag: ResMut<HandleDrag>) {
+    *drag = HandleDrag::default();
+}
+
+type EdgeQuery<'w, 's> =
+    Query<'w, 's, (&'static mut RoadEdge, &'static EdgeNodes), Without<RoadPlaceholder>>;
+
+/// Works out how the edges around a handle follow it
+struct Planner<'a, 'w, 's> {
+    graph: &'a RoadGraph,
+    edges: &'a EdgeQuery<'w, 's>,
+}
+
+impl Planner<'_, '_, '_> {
+    fn node(&self, node: Entity, commands: &mut Commands) -> Vec<Bend> {
+        let nodes = |edge: &Entity| self.edges.get(*edge).ok().map(|(_, nodes)| *nodes);
+        // Loops would have to move both ends at once
+        let mut incoming = self
+            .graph
+            .incoming(node)
+            .iter()
+            .filter(|edge| nodes(edge).is_some_and(|nodes| nodes.start != node))
+            .copied()
+            .collect::<Vec<Entity>>();
+        let outgoing = self
+            .graph
+            .outgoing(node)
+            .iter()
+            .filter(|edge| nodes(edge).is_some_and(|nodes| nodes.end != node))
+            .copied()
+            .collect::<Vec<Entity>>();
+
+        // Bends that depend on others come last
+        let mut first = Vec::new();
+        let mut second = Vec::new();
+
+        for next in outgoing {
+            let (next_edge, next_nodes) = self.edges.get(next).unwrap();
+            let continued = incoming.iter().position(|previous| {
+                let (previous_edge, _) = self.edges.get(*previous).unwrap();
+                previous_edge
+                    .end()
+                    .forward()
+                    .dot(*next_edge.start().forward())
+                    > 1.0 - TANGENT_TOLERANCE
+            });
+
+            let Some(index) = continued else {
+                first.push(self.bend(next, Follow::Backward));
+                continue;
+            };
+
+            let previous = incoming.remove(index);
+            let (_, previous_nodes) = self.edges.get(previous).unwrap();
+            let previous_fixed = self.graph.neighbors(previous_nodes.start).len() > 1;
+            let next_fixed = self.graph.neighbors(next_nodes.end).len() > 1;
+
+            if previous_fixed || !next_fixed {
+                first.push(self.bend(previous, Follow::Forward));
+                second.push(match previous_fixed && next_fixed {
+                    true => self.split(next, Follow::After(previous), None, commands),
+                    false => self.bend(next, Follow::After(previous)),
+                });
+            } else {
+                first.push(self.bend(next, Follow::Backward));
+                second.push(self.bend(previous, Follow::Before(next)));
+            }
+        }
+
+        for previous in incoming {
+            first.push(self.bend(previous, Follow::Forward));
+        }
+
+        first.extend(second);
+        first
+    }
+
+    fn midpoint(&self, edge: Entity, commands: &mut Commands) -> Vec<Bend> {
+        let Ok((_, nodes)) = self.edges.get(edge) else {
+            return Vec::new();
+        };
+
+        // The other direction of a two-way road doesn't hold its ends in place
+        let fixed = |node| self.graph.neighbors(node).len() > 1;
+        let follow = Follow::Through {
+            start_fixed: fixed(nodes.start),
+            end_fixed: fixed(nodes.end),
+        };
+        let bend = self.split(edge, follow, None, commands);
+
+        let twin = self.graph.twin(
+            |entity| self.edges.get(entity).ok().map(|(edge, _)| edge),
+            edge,
+        );
+        let (Some(twin), Some((second, node))) = (twin, bend.split) else {
+            return vec![bend];
+        };
+
+        // The other direction splits at the same node and follows the same biarc back
+        let follow = Follow::Twin {
+            first: edge,
+            second,
+        };
+        let twin_bend = self.split(twin, follow, Some(node), commands);
+        vec![bend, twin_bend]
+    }
+
+    fn bend(&self, edge: Entity, follow: Follow) -> Bend {
+        let (original, _) = self.edges.get(edge).unwrap();
+
+        Bend {
+            edge,
+            original: original.clone(),
+            follow,
+            split: None,
+        }
+    }
+
+    /// Splits the edge in half, to become a biarc. The halves meet at `node`, or at a new node
+    /// when there is none.
+    fn split(
+        &self,
+        edge: Entity,
+        follow: Follow,
+        node: Option<Entity>,
+        commands: &mut Commands,
+    ) -> Bend {
+        let mut bend = self.bend(edge, follow);
+        let nodes = *self.edges.get(edge).unwrap().1;
+
+        let (first, second) = bend.original.split(bend.original.length() * 0.5);
+        let node = node.unwrap_or_else(|| {
+            let node = spawn_node(commands, first.end().translation);
+            inherit_network_asset(commands, edge, node);
+            node
+        });
+        let split_edge = commands
+            .spawn((
+                Name::new("Road Edge"),
+                second,
+                EdgeNodes {
+                    start: node,
+                    end: nodes.end,
+                },
+            ))
+            .id();
+        commands.entity(edge).insert((
+            first,
+            EdgeNodes {
+                start: nodes.start,
+                end: node,
+            },
+        ));
+        inherit_network_asset(commands, edge, split_edge);
+
+        bend.split = Some((split_edge, node));
+        bend
+    }
+}
+
+impl Bend {
+    /// Shape of the edge with the handle at `target`, given the edges solved before it
+    fn solve(
+        &self,
+        target: Vec3,
+        solved: &HashMap<Entity, RoadEdge>,
+    ) -> (RoadEdge, Option<RoadEdge>) {
+        let original = &self.original;
+        let road_type = original.road_type();
+        let backward = |end: Transform, start: Vec3| {
+            RoadEdge::from_start_end(end, start, road_type.reversed()).reversed()
+        };
+
+        match self.follow {
+            Follow::Forward => (
+                RoadEdge::from_start_end(original.start(), target, road_type.clone()),
+                None,
+            ),
+            Follow::Backward => (backward(original.reversed().start(), target), None),
+            Follow::After(previous) => {
+                let start = solved[&previous].end();
+                match self.split {
+                    Some(_) => {
+                        let (first, second) =
+                            biarc::compute_biarc(start, original.end(), road_type.clone());
+                        (first, Some(second))
+                    }
+                    None => (
+                        RoadEdge::from_start_end(
+                            start,
+                            original.end().translation,
+                            road_type.clone(),
+                        ),
+                        None,
+                    ),
+                }
+            }
+            Follow::Before(next) => {
+                let end = solved[&next].reversed().end();
+                (backward(end, original.start().translation), None)
+            }
+            Follow::Through {
+                start_fixed,
+                end_fixed,
+            } => {
+                if end_fixed && !start_fixed {
+                    let second = backward(original.reversed().start(), target);
+                    let first = backward(second.reversed().end(), original.start().translation);
+                    return (first, Some(second));
+                }
+
+                let joint = match start_fixed && end_fixed {
+                    true => biarc_joint(original.start(), original.end(), road_type, target),
+                    false => target,
+                };
+                let first = RoadEdge::from_start_end(original.start(), joint, road_type.clone());
+                let second = RoadEdge::from_start_end(
+                    first.end(),
+                    original.end().translation,
+                    road_type.clone(),
+                );
+                (first, Some(second))
+            }
+            Follow::Twin { first, second } => {
+                (solved[&second].reversed(), Some(solved[&first].reversed()))
+            }
+        }
+    }
+}
+
+/// The point closest to `point` where the two arcs of a biarc from `start` to `end` can meet.
+/// Those points all lie on one circle through both ends, or on the line through them.
+fn biarc_joint(start: Transform, end: Transform, road_type: &RoadType, point: Vec3) -> Vec3 {
+    let (first, _) = biarc::compute_biarc(start, end, road_type.clone());
+    let origin = start.translation.xz();
+    let b = end.translation.xz() - origin;
+    let c = first.end().translation.xz() - origin;
+    let point = point.xz() - origin;
+
+    let denominator = 2.0 * b.perp_dot(c);
+    let joint = match denominator.abs() < f32::EPSILON * 1000.0 {
+        true => {
+            let along = b.normalize_or_zero();
+            along * point.dot(along)
+        }
+        false => {
+            let center = Vec2::new(
+                c.y * b.length_squared() - b.y * c.length_squared(),
+                b.x * c.length_squared() - c.x * b.length_squared(),
+            ) / denominator;
+            center + (point - center).normalize_or_zero() * center.length()
+        }
+    };
+
+    (origin + joint).extend(start.translation.y).xzy()
+}
@@ -0,0 +1,210 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{edge::RoadEdge, node::RoadNode};
+
+pub struct RoadGraphPlugin;
+impl Plugin for RoadGraphPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RoadGraph>().add_systems(
+            PostUpdate,
+            (
+                remove_edges_from_graph,
+                remove_nodes_from_graph,
+                add_edges_to_graph,
+                despawn_orphan_nodes,
+            )
+                .chain()
+                .in_set(GraphSystemSet),
+        );
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GraphSystemSet;
+
+/// The nodes an edge runs between, in driving direction
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeNodes {
+    pub start: Entity,
+    pub end: Entity,
+}
+
+/// Connectivity of the road network. Kept in sync with the [`EdgeNodes`] of all edges.
+#[derive(Resource, Debug, Default)]
+pub struct RoadGraph {
+    edges: HashMap<Entity, EdgeNodes>,
+    outgoing: HashMap<Entity, Vec<Entity>>,
+    incoming: HashMap<Entity, Vec<Entity>>,
+}
+
+impl RoadGraph {
+    pub fn insert_edge(&mut self, edge: Entity, nodes: EdgeNodes) {
+        self.remove_edge(edge);
+
+        self.edges.insert(edge, nodes);
+        self.outgoing.entry(nodes.start).or_default().push(edge);
+        self.incoming.entry(nodes.end).or_default().push(edge);
+        self.outgoing.entry(nodes.end).or_default();
+        self.incoming.entry(nodes.start).or_default();
+    }
+
+    pub fn remove_edge(&mut self, edge: Entity) -> Option<EdgeNodes> {
+        let nodes = self.edges.remove(&edge)?;
+
+        if let Some(outgoing) = self.outgoing.get_mut(&nodes.start) {
+            outgoing.retain(|e| *e != edge);
+        }
+        if let Some(incoming) = self.incoming.get_mut(&nodes.end) {
+            incoming.retain(|e| *e != edge);
+        }
+
+        self.prune_node(nodes.start);
+        self.prune_node(nodes.end);
+
+        Some(nodes)
+    }
+
+    /// Removes the node and every edge that starts or ends in it, returning those edges
+    pub fn remove_node(&mut self, node: Entity) -> Vec<Entity> {
+        let mut edges = self.outgoing.remove(&node).unwrap_or_default();
+        edges.extend(self.incoming.remove(&node).unwrap_or_default());
+
+        for edge in &edges {
+            self.remove_edge(*edge);
+        }
+
+        edges
+    }
+
+    fn prune_node(&mut self, node: Entity) {
+        if self.degree(node) == 0 {
+            self.outgoing.remove(&node);
+            self.incoming.remove(&node);
+        }
+    }
+
+    pub fn contains_node(&self, node: Entity) -> bool {
+        self.outgoing.contains_key(&node)
+    }
+
+    pub fn edge_nodes(&self, edge: Entity) -> Option<EdgeNodes> {
+        self.edges.get(&edge).copied()
+    }
+
+    /// Edges leaving the node
+    pub fn outgoing(&self, node: Entity) -> &[Entity] {
+        self.outgoing.get(&node).map_or(&[], Vec::as_slice)
+    }
+
+    /// Edges arriving at the node
+    pub fn incoming(&self, node: Entity) -> &[Entity] {
+        self.incoming.get(&node).map_or(&[], Vec::as_slice)
+    }
+
+    /// Nodes directly connected to the node by an edge in either direction
+    pub fn neighbors(&self, node: Entity) -> Vec<Entity> {
+        let mut neighbors = self
+            .outgoing(node)
+            .iter()
+            .map(|edge| self.edges[edge].end)
+            .chain(self.incoming(node).iter().map(|edge| self.edges[edge].start))
+            .filter(|neighbor| *neighbor != node)
+            .collect::<Vec<Entity>>();
+
+        neighbors.sort();
+        neighbors.dedup();
+        neighbors
+    }
+
+    /// Edge running back along `edge` between the same nodes, which two-way roads have.
+    /// `edges` looks up the geometry of an edge.
+    pub fn twin<'a>(
+        &self,
+        edges: impl Fn(Entity) -> Option<&'a RoadEdge>,
+        edge: Entity,
+    ) -> Option<Entity> {
+        let nodes = self.edge_nodes(edge)?;
+        let road_edge = edges(edge)?;
+
+        self.outgoing(nodes.end).iter().copied().find(|other| {
+            *other != edge
+                && self.edges[other].end == nodes.start
+                && edges(*other).is_some_and(|other| other.is_reverse_of(road_edge))
+        })
+    }
+
+    /// Number of edges that start or end in the node
+    pub fn degree(&self, node: Entity) -> usize {
+        self.outgoing(node).len() + self.incoming(node).len()
+    }
+
+    /// Edges that continue where the edge ends
+    pub fn next_edges(&self, edge: Entity) -> &[Entity] {
+        match self.edges.get(&edge) {
+            Some(nodes) => self.outgoing(nodes.end),
+            None => &[],
+        }
+    }
+
+    /// Edges that lead into the start of the edge
+    pub fn previous_edges(&self, edge: Entity) -> &[Entity] {
+        match self.edges.get(&edge) {
+            Some(nodes) => self.incoming(nodes.start),
+            None => &[],
+        }
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.outgoing.keys().copied()
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = (Entity, EdgeNodes)> + '_ {
+        self.edges.iter().map(|(edge, nodes)| (*edge, *nodes))
+    }
+}
+
+fn add_edges_to_graph(
+    changed_edges: Query<(Entity, &EdgeNodes), Changed<EdgeNodes>>,
+    mut graph: ResMut<RoadGraph>,
+) {
+    for (entity, nodes) in &changed_edges {
+        graph.insert_edge(entity, *nodes);
+    }
+}
+
+fn remove_edges_from_graph(
+    mut removed_edges: RemovedComponents<EdgeNodes>,
+    mut graph: ResMut<RoadGraph>,
+) {
+    for entity in removed_edges.read() {
+        graph.remove_edge(entity);
+    }
+}
+
+/// Edges of removed nodes are despawned along with them
+fn remove_nodes_from_graph(
+    mut removed_nodes: RemovedComponents<RoadNode>,
+    mut graph: ResMut<RoadGraph>,
+    mut commands: Commands,
+) {
+    for entity in removed_nodes.read() {
+        for edge in graph.remove_node(entity) {
+            if let Some(edge) = commands.get_entity(edge) {
+                edge.despawn_recursive();
+            }
+        }
+    }
+}
+
+/// Nodes that no longer connect any edge are removed from the world
+fn despawn_orphan_nodes(
+    nodes: Query<Entity, With<RoadNode>>,
+    graph: Res<RoadGraph>,
+    mut commands: Commands,
+) {
+    for node in &nodes {
+        if graph.degree(node) == 0 {
+            commands.entity(node).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use bevy::{ecs::system::SystemParam, input::common_conditions::input_just_released, prelude::*};
+
+use crate::states::GameState;
+
+use super::{
+    cross_section::DriveSide,
+    edge::RoadEdge,
+    graph::{EdgeNodes, RoadGraph},
+    network::{NetworkEdge, RoadNetwork},
+    placeholder::{spawn_node, RoadPlaceholder},
+    save::control_pressed,
+};
+
+/// Most build operations that can be undone
+pub const MAX_HISTORY: usize = 100;
+/// Nodes of a restored network this close to the end of an edge in the world are the same node
+const NODE_MATCH_DISTANCE: f32 = 1e-3;
+
+pub struct BuildHistoryPlugin;
+impl Plugin for BuildHistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BuildHistory>().add_systems(
+            Update,
+            (
+                undo.run_if(input_just_released(KeyCode::KeyZ)),
+                redo.run_if(input_just_released(KeyCode::KeyY)),
+            )
+                .run_if(control_pressed)
+                .run_if(in_state(GameState::Building)),
+        );
+    }
+}
+
+/// Networks as they were before each build operation, undone with Ctrl+Z and redone with
+/// Ctrl+Y. Every operation that changes edges records the network before changing it, so
+/// undoing puts back the edges, their nodes and through them the road graph and world tiles.
+/// Road spawners and the side traffic keeps to are not part of the history.
+#[derive(Resource, Debug, Default)]
+pub struct BuildHistory {
+    undo: Vec<RoadNetwork>,
+    redo: Vec<RoadNetwork>,
+}
+
+impl BuildHistory {
+    /// Remembers the network from before an operation. Whatever was undone can't be redone
+    /// after that.
+    pub fn record(&mut self, before: RoadNetwork) {
+        self.undo.push(before);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+
+        self.redo.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Snapshot of the edges for `record`
+    pub fn snapshot<'a>(
+        graph: &RoadGraph,
+        edges: impl Fn(Entity) -> Option<&'a RoadEdge>,
+    ) -> RoadNetwork {
+        RoadNetwork::capture(graph, edges, [], DriveSide::default())
+    }
+}
+
+/// Swaps the network in the world for one from the history
+#[derive(SystemParam)]
+struct NetworkEditor<'w, 's> {
+    graph: Res<'w, RoadGraph>,
+    edges: Query<'w, 's, (Entity, &'static RoadEdge, &'static EdgeNodes), Without<RoadPlaceholder>>,
+    placeholders: Query<'w, 's, Entity, With<RoadPlaceholder>>,
+    commands: Commands<'w, 's>,
+}
+
+impl NetworkEditor<'_, '_> {
+    fn snapshot(&self) -> RoadNetwork {
+        BuildHistory::snapshot(&self.graph, |entity| {
+            self.edges.get(entity).ok().map(|(_, edge, _)| edge)
+        })
+    }
+
+    /// Turns the edges and nodes into those of `network`, dropping the road being placed. Edges
+    /// and nodes both have in common are left alone, along with the vehicles on them. Nodes no
+    /// edge uses anymore are despawned with the road graph.
+    fn restore(&mut self, network: &RoadNetwork) {
+        if let Err(error) = network.validate() {
+            error!("Could not restore road network: {error}");
+            return;
+        }
+
+        for entity in &self.placeholders {
+            self.commands.entity(entity).despawn_recursive();
+        }
+
+        let ends = self
+            .edges
+            .iter()
+            .flat_map(|(_, edge, nodes)| {
+                [
+                    (edge.start().translation, nodes.start),
+                    (edge.end().translation, nodes.end),
+                ]
+            })
+            .collect::<Vec<(Vec3, Entity)>>();
+        let nodes = network
+            .nodes
+            .iter()
+            .map(|position| {
+                ends.iter()
+                    .find(|(end, _)| end.distance(*position) < NODE_MATCH_DISTANCE)
+                    .map(|(_, node)| *node)
+                    .unwrap_or_else(|| spawn_node(&mut self.commands, *position))
+            })
+            .collect::<Vec<Entity>>();
+
+        let mut missing = network.edges.iter().collect::<Vec<&NetworkEdge>>();
+        for (entity, edge, edge_nodes) in &self.edges {
+            let index = |node: Entity| nodes.iter().position(|n| *n == node);
+            let same = match (index(edge_nodes.start), index(edge_nodes.end)) {
+                (Some(start), Some(end)) => {
+                    let live = NetworkEdge::from_edge(start, end, edge);
+                    missing.iter().position(|edge| **edge == live)
+                }
+                _ => None,
+            };
+
+            match same {
+                Some(index) => {
+                    missing.swap_remove(index);
+                }
+                None => self.commands.entity(entity).despawn_recursive(),
+            }
+        }
+
+        for edge in missing {
+            self.commands.spawn((
+                Name::new("Road Edge"),
+                edge.to_edge(),
+                EdgeNodes {
+                    start: nodes[edge.start_node],
+                    end: nodes[edge.end_node],
+                },
+            ));
+        }
+    }
+}
+
+fn undo(mut history: ResMut<BuildHistory>, mut editor: NetworkEditor) {
+    let Some(before) = history.undo.pop() else {
+        return;
+    };
+
+    history.redo.push(editor.snapshot());
+    editor.restore(&before);
+}
+
+fn redo(mut history: ResMut<BuildHistory>, mut editor: NetworkEditor) {
+    let Some(after) = history.redo.pop() else {
+        return;
+    };
+
+    history.undo.push(editor.snapshot());
+    editor.restore(&after);
+}
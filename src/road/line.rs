@@ -1,6 +1,6 @@
 use bevy::{math::bounding::Aabb3d, prelude::*};
 
-use super::{RoadEdge, ROAD_WIDTH};
+use super::{collision::Centerline, RoadEdge, ROAD_WIDTH};
 
 #[derive(Component)]
 pub struct LineEdge {
@@ -14,6 +14,27 @@ pub struct LineEdge {
 }
 
 impl LineEdge {
+    pub fn from_start_end(start: Vec2, end: Vec2, lanes: u8) -> Self {
+        let length = start.distance(end);
+        let tangent = (end - start).normalize_or_zero();
+
+        let half_width = lanes as f32 * ROAD_WIDTH * 0.5;
+        let min = start.min(end) - half_width;
+        let max = start.max(end) + half_width;
+
+        Self {
+            start,
+            tangent,
+            end,
+            length,
+            lanes,
+            aabb3: Aabb3d {
+                min: Vec3::new(min.x, -0.1, min.y),
+                max: Vec3::new(max.x, 0.1, max.y),
+            },
+        }
+    }
+
     pub fn start(&self) -> Vec2 {
         self.start
     }
@@ -33,6 +54,13 @@ impl LineEdge {
     pub fn aabb3(&self) -> Aabb3d {
         self.aabb3
     }
+
+    pub fn centerline(&self) -> Centerline {
+        Centerline::Segment {
+            start: self.start,
+            end: self.start + self.tangent * self.length,
+        }
+    }
 }
 
 impl RoadEdge for LineEdge {
@@ -45,12 +73,12 @@ impl RoadEdge for LineEdge {
     fn intersects_point(&self, point: Vec2) -> bool {
         let road_thickness = self.lanes as f32 * ROAD_WIDTH * 0.5;
 
-        let projection_length = point.dot(self.tangent);
+        let projection_length = (point - self.start).dot(self.tangent);
         if projection_length < -road_thickness || projection_length > self.length + road_thickness {
             return false;
         }
 
-        let closest_point_on_line = projection_length * self.tangent;
+        let closest_point_on_line = self.start + projection_length * self.tangent;
         let vector_to_line = closest_point_on_line - point;
         let distance = vector_to_line.length();
 
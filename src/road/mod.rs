@@ -3,13 +3,35 @@ use bevy::{math::bounding::Aabb3d, prelude::*};
 use crate::states::GameState;
 
 use self::{
-    placeholder::{BuildSystemSet, RoadPlaceholder},
+    asset::RoadNetworkAssetPlugin,
+    bulldoze::BulldozePlugin,
+    cross_section::DriveSide,
+    edit::EditPlugin,
+    graph::RoadGraphPlugin,
+    history::BuildHistoryPlugin,
+    node::RoadNode,
+    occupancy::LaneOccupancyPlugin,
+    palette::RoadPalettePlugin,
+    placeholder::{BuildSystemSet, BuildTool, RoadPlaceholder},
+    save::SaveLoadPlugin,
     world::{RoadGridPlugin, WorldSystemSet, WorldTile},
 };
 
+pub mod asset;
 pub mod biarc;
+pub mod bulldoze;
+pub mod cross_section;
 pub mod edge;
+pub mod edit;
+pub mod graph;
+pub mod history;
+pub mod network;
+pub mod node;
+pub mod occupancy;
+pub mod palette;
 pub mod placeholder;
+pub mod route;
+pub mod save;
 pub mod world;
 
 pub mod arc;
@@ -18,16 +40,32 @@ pub mod line;
 
 pub const ROAD_WIDTH: f32 = 1.0;
 
+/// Everything needed to build and render roads
 pub struct RoadPlugin;
 impl Plugin for RoadPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<WorldTile>()
-            .add_plugins((RoadGridPlugin, placeholder::PlaceholderPlugin))
+            .add_plugins((
+                RoadNetworkPlugin,
+                RoadGridPlugin,
+                placeholder::PlaceholderPlugin,
+                SaveLoadPlugin,
+                RoadNetworkAssetPlugin,
+                BuildHistoryPlugin,
+                BulldozePlugin,
+                EditPlugin,
+                RoadPalettePlugin,
+            ))
+            .init_resource::<BuildTool>()
+            .add_systems(Update, attach_spawner_meshes)
             .configure_sets(
                 Update,
                 (
                     BuildSystemSet::Building.run_if(any_with_component::<RoadPlaceholder>),
-                    BuildSystemSet::NotBuilding.run_if(not(any_with_component::<RoadPlaceholder>)),
+                    BuildSystemSet::NotBuilding
+                        .run_if(not(any_with_component::<RoadPlaceholder>))
+                        .run_if(resource_equals(BuildTool::Road)),
+                    BuildSystemSet::Bulldozing.run_if(resource_equals(BuildTool::Bulldoze)),
                     WorldSystemSet,
                 )
                     .chain()
@@ -38,9 +76,44 @@ impl Plugin for RoadPlugin {
     }
 }
 
+/// Connectivity and lane bookkeeping of the road network, without any rendering or input, so
+/// it can run headless
+pub struct RoadNetworkPlugin;
+impl Plugin for RoadNetworkPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<RoadNode>()
+            .register_type::<DriveSide>()
+            .init_resource::<DriveSide>()
+            .add_plugins((RoadGraphPlugin, LaneOccupancyPlugin));
+    }
+}
+
 #[derive(Component)]
 pub struct RoadSpawner;
 
+/// Gives road spawners that were loaded from a file something to see and click on
+fn attach_spawner_meshes(
+    spawners: Query<Entity, (Added<RoadSpawner>, Without<Handle<Mesh>>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    if spawners.is_empty() {
+        return;
+    }
+
+    let cuboid = Cuboid::from_size(Vec3::ONE).mesh();
+    let aabb = cuboid.compute_aabb().unwrap_or_default();
+    let mesh = meshes.add(cuboid);
+    let material = materials.add(Color::rgb(1.0, 0.0, 0.0));
+
+    for entity in &spawners {
+        commands
+            .entity(entity)
+            .insert((mesh.clone(), material.clone(), aabb));
+    }
+}
+
 pub trait RoadEdge {
     fn interpolate(&self, length: f32, lane_offset: f32) -> Transform;
     fn intersects_point(&self, point: Vec2) -> bool;
@@ -0,0 +1,796 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{
+    cross_section::DriveSide,
+    edge::{RoadEdge, Twist},
+    graph::{EdgeNodes, RoadGraph},
+    node::RoadNode,
+    palette::RoadType,
+    RoadSpawner,
+};
+
+/// Layout version of the network files written now. When the layout changes, bump it, keep
+/// the old layout in `legacy` and have it migrate to the new one.
+pub const NETWORK_VERSION: u32 = 7;
+/// Edge ends closer than this share a node when a [`NetworkBuilder`] adds edges one by one
+const NODE_MERGE_DISTANCE: f32 = 0.05;
+
+/// Road network as stored on disk. Edges refer to their nodes by index.
+#[derive(Asset, TypePath, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoadNetwork {
+    pub version: u32,
+    /// Position of every node
+    pub nodes: Vec<Vec3>,
+    pub edges: Vec<NetworkEdge>,
+    pub spawners: Vec<Transform>,
+    /// Side traffic keeps to, which the cross-sections of the edges are laid out for
+    pub drive_side: DriveSide,
+}
+
+impl Default for RoadNetwork {
+    fn default() -> Self {
+        Self {
+            version: NETWORK_VERSION,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            spawners: Vec::new(),
+            drive_side: DriveSide::Right,
+        }
+    }
+}
+
+/// Just the version of a network file, checked before reading the rest. Files from before
+/// versioning have none.
+#[derive(Deserialize)]
+struct FileVersion {
+    #[serde(default = "first_version")]
+    version: u32,
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+/// File formats networks can be stored in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkFormat {
+    Ron,
+    Json,
+}
+
+impl NetworkFormat {
+    /// Picks the format from the file extension, such as `.road.ron` or `.json`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "ron" => Some(Self::Ron),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    fn parse<T: DeserializeOwned>(self, text: &str) -> Result<T, NetworkError> {
+        match self {
+            Self::Ron => {
+                ron::from_str(text).map_err(|error| NetworkError::Format(error.to_string()))
+            }
+            Self::Json => {
+                serde_json::from_str(text).map_err(|error| NetworkError::Format(error.to_string()))
+            }
+        }
+    }
+
+    fn write<T: Serialize>(self, value: &T) -> Result<String, NetworkError> {
+        match self {
+            Self::Ron => ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+                .map_err(|error| NetworkError::Format(error.to_string())),
+            Self::Json => serde_json::to_string_pretty(value)
+                .map_err(|error| NetworkError::Format(error.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkEdge {
+    pub start_node: usize,
+    pub end_node: usize,
+    pub start: Transform,
+    pub end: Transform,
+    pub center: Vec3,
+    pub radius: f32,
+    pub length: f32,
+    pub twist: Twist,
+    pub road_type: RoadType,
+}
+
+impl NetworkEdge {
+    pub fn from_edge(start_node: usize, end_node: usize, edge: &RoadEdge) -> Self {
+        Self {
+            start_node,
+            end_node,
+            start: edge.start(),
+            end: edge.end(),
+            center: edge.center(),
+            radius: edge.radius(),
+            length: edge.length(),
+            twist: edge.twist(),
+            road_type: edge.road_type().clone(),
+        }
+    }
+
+    pub fn to_edge(&self) -> RoadEdge {
+        RoadEdge::new(
+            self.start,
+            self.end,
+            self.center,
+            self.radius,
+            self.length,
+            self.twist,
+            self.road_type.clone(),
+        )
+    }
+}
+
+/// Layouts of older network files. Each one migrates to the layout after it, so a file of any
+/// older version reaches the current layout one step at a time.
+pub mod legacy {
+    use bevy::prelude::*;
+    use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+    use super::{NetworkEdge, NetworkError, NetworkFormat, RoadNetwork, NETWORK_VERSION};
+    use crate::road::{
+        cross_section::{CrossSection, DriveSide, LaneKind, LaneProfile},
+        edge::Twist,
+        palette::{RoadTexture, RoadType, DEFAULT_SPEED_LIMIT},
+        ROAD_WIDTH,
+    };
+
+    /// Reads a file of an older `version` and migrates it to the current layout
+    pub fn migrate(
+        version: u32,
+        text: &str,
+        format: NetworkFormat,
+    ) -> Result<RoadNetwork, NetworkError> {
+        fn read<L: Layout>(text: &str, format: NetworkFormat) -> Result<RoadNetwork, NetworkError> {
+            Ok(format.parse::<L>(text)?.migrate())
+        }
+
+        match version {
+            1 => read::<NetworkV1>(text, format),
+            2 => read::<NetworkV2>(text, format),
+            3 => read::<NetworkV3>(text, format),
+            4 => read::<NetworkV4>(text, format),
+            5 => read::<NetworkV5>(text, format),
+            6 => read::<NetworkV6>(text, format),
+            _ => Err(NetworkError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// An older layout, which knows how to reach the current one
+    pub trait Layout: DeserializeOwned {
+        fn migrate(self) -> RoadNetwork;
+    }
+
+    /// First layout, without a version or spawners
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct NetworkV1 {
+        pub nodes: Vec<Vec3>,
+        pub edges: Vec<EdgeV2>,
+    }
+
+    impl Layout for NetworkV1 {
+        fn migrate(self) -> RoadNetwork {
+            NetworkV2 {
+                version: 2,
+                nodes: self.nodes,
+                edges: self.edges,
+                spawners: Vec::new(),
+            }
+            .migrate()
+        }
+    }
+
+    /// Second layout, with edges that only know their lane count
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct NetworkV2 {
+        pub version: u32,
+        pub nodes: Vec<Vec3>,
+        pub edges: Vec<EdgeV2>,
+        pub spawners: Vec<Transform>,
+    }
+
+    impl Layout for NetworkV2 {
+        fn migrate(self) -> RoadNetwork {
+            NetworkV3 {
+                version: 3,
+                nodes: self.nodes,
+                edges: self.edges.into_iter().map(EdgeV3::from).collect(),
+                spawners: self.spawners,
+            }
+            .migrate()
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct EdgeV2 {
+        pub start_node: usize,
+        pub end_node: usize,
+        pub start: Transform,
+        pub end: Transform,
+        pub center: Vec3,
+        pub radius: f32,
+        pub length: f32,
+        pub twist: Twist,
+        pub lanes: u8,
+    }
+
+    impl From<EdgeV2> for EdgeV3 {
+        fn from(edge: EdgeV2) -> Self {
+            Self {
+                start_node: edge.start_node,
+                end_node: edge.end_node,
+                start: edge.start,
+                end: edge.end,
+                center: edge.center,
+                radius: edge.radius,
+                length: edge.length,
+                twist: edge.twist,
+                road_type: RoadTypeV3 {
+                    lanes: edge.lanes,
+                    lane_width: ROAD_WIDTH,
+                    one_way: false,
+                    speed_limit: DEFAULT_SPEED_LIMIT,
+                    texture: RoadTexture::Asphalt,
+                },
+            }
+        }
+    }
+
+    /// Third layout, with road types whose lanes all have the same width
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct NetworkV3 {
+        pub version: u32,
+        pub nodes: Vec<Vec3>,
+        pub edges: Vec<EdgeV3>,
+        pub spawners: Vec<Transform>,
+    }
+
+    impl Layout for NetworkV3 {
+        fn migrate(self) -> RoadNetwork {
+            NetworkV4 {
+                version: 4,
+                nodes: self.nodes,
+                edges: self.edges.into_iter().map(EdgeV4::from).collect(),
+                spawners: self.spawners,
+            }
+            .migrate()
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct EdgeV3 {
+        pub start_node: usize,
+        pub end_node: usize,
+        pub start: Transform,
+        pub end: Transform,
+        pub center: Vec3,
+        pub radius: f32,
+        pub length: f32,
+        pub twist: Twist,
+        pub road_type: RoadTypeV3,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct RoadTypeV3 {
+        pub lanes: u8,
+        pub lane_width: f32,
+        pub one_way: bool,
+        pub speed_limit: f32,
+        pub texture: RoadTexture,
+    }
+
+    impl From<EdgeV3> for EdgeV4 {
+        fn from(edge: EdgeV3) -> Self {
+            let road_type = edge.road_type;
+            let lane = LaneProfileV4 {
+                kind: LaneKind::Driving,
+                width: road_type.lane_width,
+            };
+
+            Self {
+                start_node: edge.start_node,
+                end_node: edge.end_node,
+                start: edge.start,
+                end: edge.end,
+                center: edge.center,
+                radius: edge.radius,
+                length: edge.length,
+                twist: edge.twist,
+                road_type: RoadTypeV4 {
+                    cross_section: CrossSectionV4 {
+                        lanes: vec![lane; road_type.lanes as usize],
+                    },
+                    one_way: road_type.one_way,
+                    speed_limit: road_type.speed_limit,
+                    texture: road_type.texture,
+                },
+            }
+        }
+    }
+
+    /// Fourth layout, with cross-sections whose lanes all drive along the edge. Two-way roads
+    /// are twin edges that each have all lanes.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct NetworkV4 {
+        pub version: u32,
+        pub nodes: Vec<Vec3>,
+        pub edges: Vec<EdgeV4>,
+        pub spawners: Vec<Transform>,
+    }
+
+    impl Layout for NetworkV4 {
+        fn migrate(self) -> RoadNetwork {
+            NetworkV5 {
+                version: 5,
+                nodes: self.nodes,
+                edges: self.edges.into_iter().map(NetworkEdge::from).collect(),
+                spawners: self.spawners,
+            }
+            .migrate()
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct EdgeV4 {
+        pub start_node: usize,
+        pub end_node: usize,
+        pub start: Transform,
+        pub end: Transform,
+        pub center: Vec3,
+        pub radius: f32,
+        pub length: f32,
+        pub twist: Twist,
+        pub road_type: RoadTypeV4,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct RoadTypeV4 {
+        pub cross_section: CrossSectionV4,
+        pub one_way: bool,
+        pub speed_limit: f32,
+        pub texture: RoadTexture,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct CrossSectionV4 {
+        pub lanes: Vec<LaneProfileV4>,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub struct LaneProfileV4 {
+        pub kind: LaneKind,
+        pub width: f32,
+    }
+
+    impl From<EdgeV4> for NetworkEdge {
+        fn from(edge: EdgeV4) -> Self {
+            let road_type = edge.road_type;
+            let lanes = road_type
+                .cross_section
+                .lanes
+                .iter()
+                .map(|lane| LaneProfile::new(lane.kind, lane.width))
+                .collect();
+
+            Self {
+                start_node: edge.start_node,
+                end_node: edge.end_node,
+                start: edge.start,
+                end: edge.end,
+                center: edge.center,
+                radius: edge.radius,
+                length: edge.length,
+                twist: edge.twist,
+                road_type: RoadType {
+                    cross_section: CrossSection { lanes },
+                    speed_limit: road_type.speed_limit,
+                    texture: road_type.texture,
+                },
+            }
+        }
+    }
+
+    /// Fifth layout, from before networks knew which side traffic keeps to. All of them have
+    /// right-hand traffic.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct NetworkV5 {
+        pub version: u32,
+        pub nodes: Vec<Vec3>,
+        pub edges: Vec<NetworkEdge>,
+        pub spawners: Vec<Transform>,
+    }
+
+    impl Layout for NetworkV5 {
+        fn migrate(self) -> RoadNetwork {
+            NetworkV6 {
+                version: 6,
+                nodes: self.nodes,
+                edges: self.edges,
+                spawners: self.spawners,
+                drive_side: DriveSide::Right,
+            }
+            .migrate()
+        }
+    }
+
+    /// Sixth layout, from before lanes could taper. Its lanes read with no end width.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct NetworkV6 {
+        pub version: u32,
+        pub nodes: Vec<Vec3>,
+        pub edges: Vec<NetworkEdge>,
+        pub spawners: Vec<Transform>,
+        pub drive_side: DriveSide,
+    }
+
+    impl Layout for NetworkV6 {
+        fn migrate(self) -> RoadNetwork {
+            RoadNetwork {
+                version: NETWORK_VERSION,
+                nodes: self.nodes,
+                edges: self.edges,
+                spawners: self.spawners,
+                drive_side: self.drive_side,
+            }
+        }
+    }
+}
+
+/// Builds a network edge by edge, for formats without a node list. Edge ends closer than
+/// `NODE_MERGE_DISTANCE` share a node.
+#[derive(Debug, Clone, Default)]
+pub struct NetworkBuilder {
+    pub network: RoadNetwork,
+    /// Nodes by the grid cell they lie in, with cells `NODE_MERGE_DISTANCE` wide
+    cells: HashMap<IVec3, Vec<usize>>,
+}
+
+impl NetworkBuilder {
+    pub fn new(network: RoadNetwork) -> Self {
+        let mut builder = Self {
+            network,
+            cells: HashMap::new(),
+        };
+        for (index, node) in builder.network.nodes.iter().enumerate() {
+            builder.cells.entry(cell(*node)).or_default().push(index);
+        }
+
+        builder
+    }
+
+    /// Adds an edge, connecting it to the nodes already at its ends or adding new ones
+    pub fn push_edge(&mut self, edge: &RoadEdge) {
+        let start_node = self.node_at(edge.start().translation);
+        let end_node = self.node_at(edge.end().translation);
+
+        self.network
+            .edges
+            .push(NetworkEdge::from_edge(start_node, end_node, edge));
+    }
+
+    fn node_at(&mut self, position: Vec3) -> usize {
+        let center = cell(position);
+
+        // Nodes close enough to merge with lie in the cell of the position or next to it
+        let nearby = (-1..=1)
+            .flat_map(|x| (-1..=1).flat_map(move |y| (-1..=1).map(move |z| IVec3::new(x, y, z))))
+            .filter_map(|offset| self.cells.get(&(center + offset)))
+            .flatten()
+            .copied()
+            .filter(|index| self.network.nodes[*index].distance(position) < NODE_MERGE_DISTANCE)
+            .min();
+
+        match nearby {
+            Some(index) => index,
+            None => {
+                self.network.nodes.push(position);
+                let index = self.network.nodes.len() - 1;
+                self.cells.entry(center).or_default().push(index);
+                index
+            }
+        }
+    }
+}
+
+fn cell(position: Vec3) -> IVec3 {
+    (position / NODE_MERGE_DISTANCE).floor().as_ivec3()
+}
+
+/// Entities spawned for a network, in the order of the network file
+#[derive(Debug, Clone, Default)]
+pub struct NetworkEntities {
+    pub nodes: Vec<Entity>,
+    pub edges: Vec<Entity>,
+    pub spawners: Vec<Entity>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkError {
+    /// An edge refers to a node that does not exist
+    MissingNode {
+        edge: usize,
+        node: usize,
+    },
+    /// The cross-section of an edge has no lane vehicles can drive on
+    NoDrivingLanes(usize),
+    /// The file has a version with no known layout, such as one written by a newer version
+    UnsupportedVersion(u32),
+    UnknownFormat(PathBuf),
+    Io(String),
+    /// The contents are not a valid network in the format of the file
+    Format(String),
+}
+
+impl std::fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkError::MissingNode { edge, node } => {
+                write!(f, "edge {edge} refers to missing node {node}")
+            }
+            NetworkError::NoDrivingLanes(edge) => write!(f, "edge {edge} has no driving lanes"),
+            NetworkError::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "network version {version} is not supported, only versions 1 to {NETWORK_VERSION} are"
+                )
+            }
+            NetworkError::UnknownFormat(path) => {
+                write!(f, "{} has no known network file extension", path.display())
+            }
+            NetworkError::Io(error) | NetworkError::Format(error) => f.write_str(error),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+impl RoadNetwork {
+    /// Collects the edges of the road graph and the road spawners. Nodes are numbered in the
+    /// order edges reach them.
+    pub fn capture<'a>(
+        graph: &RoadGraph,
+        edges: impl Fn(Entity) -> Option<&'a RoadEdge>,
+        spawners: impl IntoIterator<Item = Transform>,
+        drive_side: DriveSide,
+    ) -> Self {
+        let mut graph_edges = graph.edges().collect::<Vec<(Entity, EdgeNodes)>>();
+        graph_edges.sort_by_key(|(entity, _)| *entity);
+
+        let mut network = RoadNetwork {
+            spawners: spawners.into_iter().collect(),
+            drive_side,
+            ..default()
+        };
+        let mut node_indices = HashMap::<Entity, usize>::new();
+
+        for (entity, nodes) in graph_edges {
+            let Some(edge) = edges(entity) else {
+                continue;
+            };
+
+            let mut index = |node: Entity, position: Vec3| {
+                *node_indices.entry(node).or_insert_with(|| {
+                    network.nodes.push(position);
+                    network.nodes.len() - 1
+                })
+            };
+
+            let start_node = index(nodes.start, edge.start().translation);
+            let end_node = index(nodes.end, edge.end().translation);
+
+            network
+                .edges
+                .push(NetworkEdge::from_edge(start_node, end_node, edge));
+        }
+
+        network
+    }
+
+    /// Reads a network, migrating files of older versions to the current layout and refusing
+    /// files of newer ones
+    pub fn read(text: &str, format: NetworkFormat) -> Result<Self, NetworkError> {
+        let FileVersion { version } = format.parse(text)?;
+
+        match version {
+            NETWORK_VERSION => format.parse(text),
+            _ => legacy::migrate(version, text, format),
+        }
+    }
+
+    pub fn write(&self, format: NetworkFormat) -> Result<String, NetworkError> {
+        format.write(self)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, NetworkError> {
+        let format = NetworkFormat::from_path(path)
+            .ok_or_else(|| NetworkError::UnknownFormat(path.to_path_buf()))?;
+        let text = fs::read_to_string(path)
+            .map_err(|error| NetworkError::Io(format!("{}: {error}", path.display())))?;
+
+        Self::read(&text, format)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), NetworkError> {
+        let format = NetworkFormat::from_path(path)
+            .ok_or_else(|| NetworkError::UnknownFormat(path.to_path_buf()))?;
+
+        fs::write(path, self.write(format)?)
+            .map_err(|error| NetworkError::Io(format!("{}: {error}", path.display())))
+    }
+
+    pub fn validate(&self) -> Result<(), NetworkError> {
+        for (index, edge) in self.edges.iter().enumerate() {
+            for node in [edge.start_node, edge.end_node] {
+                if node >= self.nodes.len() {
+                    return Err(NetworkError::MissingNode { edge: index, node });
+                }
+            }
+
+            if edge.road_type.lanes() == 0 {
+                return Err(NetworkError::NoDrivingLanes(index));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Spawns the nodes and edges of the network. The road graph picks the edges up through
+    /// their `EdgeNodes`.
+    pub fn spawn(&self, commands: &mut Commands) -> Result<NetworkEntities, NetworkError> {
+        self.validate()?;
+
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|position| {
+                commands
+                    .spawn((
+                        Name::new("Road Node"),
+                        TransformBundle::from_transform(Transform::from_translation(*position)),
+                        RoadNode,
+                    ))
+                    .id()
+            })
+            .collect::<Vec<Entity>>();
+
+        let edges = self
+            .edges
+            .iter()
+            .map(|edge| {
+                commands
+                    .spawn((
+                        Name::new("Road Edge"),
+                        edge.to_edge(),
+                        EdgeNodes {
+                            start: nodes[edge.start_node],
+                            end: nodes[edge.end_node],
+                        },
+                    ))
+                    .id()
+            })
+            .collect::<Vec<Entity>>();
+
+        let spawners = self
+            .spawners
+            .iter()
+            .map(|transform| {
+                commands
+                    .spawn((
+                        Name::new("Road Spawner"),
+                        SpatialBundle::from_transform(*transform),
+                        RoadSpawner,
+                    ))
+                    .id()
+            })
+            .collect::<Vec<Entity>>();
+
+        Ok(NetworkEntities {
+            nodes,
+            edges,
+            spawners,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::road::{cross_section::LaneKind, palette::DEFAULT_SPEED_LIMIT, ROAD_WIDTH};
+
+    const EDGE: &str = "(
+        start_node: 0,
+        end_node: 1,
+        start: (
+            translation: (0.0, 0.0, 0.0),
+            rotation: (0.0, 0.70710677, 0.0, -0.70710677),
+            scale: (1.0, 1.0, 1.0),
+        ),
+        end: (
+            translation: (10.0, 0.0, 0.0),
+            rotation: (0.0, 0.70710677, 0.0, -0.70710677),
+            scale: (1.0, 1.0, 1.0),
+        ),
+        center: (5.0, 0.0, 0.0),
+        radius: 0.0,
+        length: 10.0,
+        twist: Straight,
+        lanes: 2,
+    )";
+
+    fn assert_migrated(network: &RoadNetwork) {
+        assert_eq!(network.version, NETWORK_VERSION);
+        assert_eq!(network.nodes.len(), 2);
+        assert_eq!(network.drive_side, DriveSide::Right);
+
+        let [edge] = network.edges.as_slice() else {
+            panic!("expected one edge, got {:?}", network.edges);
+        };
+        assert_eq!((edge.start_node, edge.end_node), (0, 1));
+        assert_eq!(edge.length, 10.0);
+        assert_eq!(edge.road_type.speed_limit, DEFAULT_SPEED_LIMIT);
+        assert_eq!(edge.road_type.cross_section.lanes.len(), 2);
+        for lane in &edge.road_type.cross_section.lanes {
+            assert_eq!(lane.kind, LaneKind::Driving);
+            assert_eq!(lane.width, ROAD_WIDTH);
+            assert_eq!(lane.end_width, None);
+        }
+    }
+
+    #[test]
+    fn second_layout_migrates_to_current() {
+        let text = format!(
+            "(version: 2, nodes: [(0.0, 0.0, 0.0), (10.0, 0.0, 0.0)], edges: [{EDGE}], spawners: [])"
+        );
+
+        let network = RoadNetwork::read(&text, NetworkFormat::Ron).unwrap();
+
+        assert_migrated(&network);
+        assert!(network.spawners.is_empty());
+    }
+
+    #[test]
+    fn file_without_version_reads_as_first_layout() {
+        let text = format!("(nodes: [(0.0, 0.0, 0.0), (10.0, 0.0, 0.0)], edges: [{EDGE}])");
+
+        assert_migrated(&RoadNetwork::read(&text, NetworkFormat::Ron).unwrap());
+    }
+
+    #[test]
+    fn migrated_network_round_trips() {
+        let text = format!("(version: 2, nodes: [(0.0, 0.0, 0.0), (10.0, 0.0, 0.0)], edges: [{EDGE}], spawners: [])");
+        let network = RoadNetwork::read(&text, NetworkFormat::Ron).unwrap();
+
+        for format in [NetworkFormat::Ron, NetworkFormat::Json] {
+            let written = network.write(format).unwrap();
+            assert_eq!(RoadNetwork::read(&written, format).unwrap(), network);
+        }
+    }
+
+    #[test]
+    fn newer_version_is_refused() {
+        let text = format!(
+            "(version: {}, nodes: [], edges: [], spawners: [])",
+            NETWORK_VERSION + 1
+        );
+
+        assert_eq!(
+            RoadNetwork::read(&text, NetworkFormat::Ron),
+            Err(NetworkError::UnsupportedVersion(NETWORK_VERSION + 1))
+        );
+    }
+}
@@ -0,0 +1,7 @@
+use bevy::prelude::*;
+
+/// Point in the road network where edges meet. Which edges meet here is kept in the
+/// [`RoadGraph`](super::graph::RoadGraph).
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct RoadNode;
@@ -0,0 +1,420 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{edge::RoadEdge, graph::RoadGraph};
+
+pub struct LaneOccupancyPlugin;
+impl Plugin for LaneOccupancyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LaneOccupancy>()
+            .add_systems(PostUpdate, remove_edge_occupancy);
+    }
+}
+
+/// Stretch `[start, end]` of a lane claimed by an agent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Occupant {
+    pub agent: Entity,
+    pub start: f32,
+    pub end: f32,
+}
+
+/// Position on a single lane of an edge
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LanePosition {
+    pub edge: Entity,
+    pub lane: u8,
+    pub length: f32,
+}
+
+/// Occupant found when looking along a lane
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sighting {
+    pub occupant: Occupant,
+    pub edge: Entity,
+    pub lane: u8,
+    /// Distance along the lane to the occupant, negative when it overlaps the query position
+    pub gap: f32,
+}
+
+/// How a search ahead continues past the end of an edge
+#[derive(Debug, Clone, Copy)]
+pub struct Lookahead<R, J> {
+    /// Edges to follow, after which the search continues on the only edge leaving the end node
+    pub route: R,
+    /// Length of the path through the junction from a lane of an edge onto the next edge, and
+    /// the lane it arrives on. Agents on that path hold the stretch of the lane just before the
+    /// start of the next edge. Without a path, lanes keep their index, limited to the lanes the
+    /// next edge has.
+    pub junction: J,
+}
+
+/// Register of which agents occupy which part of every lane
+#[derive(Resource, Debug, Default)]
+pub struct LaneOccupancy {
+    lanes: HashMap<(Entity, u8), Vec<Occupant>>,
+    agents: HashMap<Entity, Vec<(Entity, u8)>>,
+}
+
+impl LaneOccupancy {
+    /// Claims `[start, end]` of the lane for the agent. An agent can hold several stretches,
+    /// for instance while changing lanes or crossing into the next edge.
+    pub fn register(&mut self, edge: Entity, lane: u8, agent: Entity, start: f32, end: f32) {
+        let occupant = Occupant {
+            agent,
+            start: start.min(end),
+            end: start.max(end),
+        };
+
+        let occupants = self.lanes.entry((edge, lane)).or_default();
+        let index = occupants.partition_point(|other| other.start <= occupant.start);
+        occupants.insert(index, occupant);
+
+        self.agents.entry(agent).or_default().push((edge, lane));
+    }
+
+    /// Releases every stretch held by the agent
+    pub fn unregister(&mut self, agent: Entity) {
+        let Some(lanes) = self.agents.remove(&agent) else {
+            return;
+        };
+
+        for lane in lanes {
+            if let Some(occupants) = self.lanes.get_mut(&lane) {
+                occupants.retain(|occupant| occupant.agent != agent);
+            }
+        }
+    }
+
+    pub fn remove_edge(&mut self, edge: Entity) {
+        self.lanes.retain(|(lane_edge, _), _| *lane_edge != edge);
+        for lanes in self.agents.values_mut() {
+            lanes.retain(|(lane_edge, _)| *lane_edge != edge);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lanes.clear();
+        self.agents.clear();
+    }
+
+    /// Occupants of the lane, ordered by start
+    pub fn occupants(&self, edge: Entity, lane: u8) -> &[Occupant] {
+        self.lanes.get(&(edge, lane)).map_or(&[], Vec::as_slice)
+    }
+
+    /// The first occupant ahead of `from` within `distance`, ignoring `agent`. When the edge
+    /// ends, the search continues on the only edge leaving the end node, in the lane with the
+    /// same index, limited to the lanes the next edge has.
+    pub fn ahead<'a>(
+        &self,
+        graph: &RoadGraph,
+        edges: impl Fn(Entity) -> Option<&'a RoadEdge>,
+        from: LanePosition,
+        distance: f32,
+        agent: Option<Entity>,
+    ) -> Option<Sighting> {
+        let lookahead = Lookahead {
+            route: [],
+            junction: |_, _, _| None,
+        };
+
+        self.ahead_along_route(graph, edges, from, distance, agent, lookahead)
+    }
+
+    /// Like [`Self::ahead`], but past the end of an edge the search follows the route and the
+    /// junction paths of `lookahead`
+    pub fn ahead_along_route<'a>(
+        &self,
+        graph: &RoadGraph,
+        edges: impl Fn(Entity) -> Option<&'a RoadEdge>,
+        from: LanePosition,
+        distance: f32,
+        agent: Option<Entity>,
+        lookahead: Lookahead<
+            impl IntoIterator<Item = Entity>,
+            impl Fn(Entity, u8, Entity) -> Option<(f32, u8)>,
+        >,
+    ) -> Option<Sighting> {
+        let Lookahead { route, junction } = lookahead;
+        let mut route = route.into_iter();
+        let mut edge = from.edge;
+        let mut lane = from.lane;
+        let mut position = from.length;
+        let mut travelled = 0.0;
+
+        loop {
+            let found = self
+                .occupants(edge, lane)
+                .iter()
+                .find(|occupant| Some(occupant.agent) != agent && occupant.end > position);
+
+            if let Some(occupant) = found {
+                let gap = travelled + occupant.start - position;
+                return match gap <= distance {
+                    true => Some(Sighting {
+                        occupant: *occupant,
+                        edge,
+                        lane,
+                        gap,
+                    }),
+                    false => None,
+                };
+            }
+
+            travelled += edges(edge)?.length() - position;
+            if travelled > distance {
+                return None;
+            }
+
+            let next = match route.next() {
+                Some(next) => next,
+                None => match graph.next_edges(edge) {
+                    [next] => *next,
+                    _ => return None,
+                },
+            };
+            (lane, position) = match junction(edge, lane, next) {
+                Some((length, to_lane)) => (to_lane, -length),
+                None => (lane.min(edges(next)?.lanes().saturating_sub(1)), 0.0),
+            };
+            edge = next;
+        }
+    }
+
+    /// The first occupant behind `from` within `distance`, ignoring `agent`. When the start of
+    /// the edge is reached, the search continues on the only edge entering the start node.
+    pub fn behind<'a>(
+        &self,
+        graph: &RoadGraph,
+        edges: impl Fn(Entity) -> Option<&'a RoadEdge>,
+        from: LanePosition,
+        distance: f32,
+        agent: Option<Entity>,
+    ) -> Option<Sighting> {
+        let mut edge = from.edge;
+        let mut lane = from.lane;
+        let mut position = from.length;
+        let mut travelled = 0.0;
+
+        loop {
+            let found = self
+                .occupants(edge, lane)
+                .iter()
+                .filter(|occupant| Some(occupant.agent) != agent && occupant.start < position)
+                .max_by(|a, b| a.end.total_cmp(&b.end));
+
+            if let Some(occupant) = found {
+                let gap = travelled + position - occupant.end;
+                return match gap <= distance {
+                    true => Some(Sighting {
+                        occupant: *occupant,
+                        edge,
+                        lane,
+                        gap,
+                    }),
+                    false => None,
+                };
+            }
+
+            travelled += position;
+            if travelled > distance {
+                return None;
+            }
+
+            edge = match graph.previous_edges(edge) {
+                [previous] => *previous,
+                _ => return None,
+            };
+            let previous = edges(edge)?;
+            lane = lane.min(previous.lanes().saturating_sub(1));
+            position = previous.length();
+        }
+    }
+}
+
+fn remove_edge_occupancy(
+    mut removed_edges: RemovedComponents<RoadEdge>,
+    mut occupancy: ResMut<LaneOccupancy>,
+) {
+    for entity in removed_edges.read() {
+        occupancy.remove_edge(entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::road::{graph::EdgeNodes, palette::RoadType};
+
+    const A: Entity = Entity::from_raw(0);
+    const B: Entity = Entity::from_raw(1);
+    const C: Entity = Entity::from_raw(2);
+    const D: Entity = Entity::from_raw(3);
+    const AGENT: Entity = Entity::from_raw(10);
+    const OTHER: Entity = Entity::from_raw(11);
+
+    /// Edges 10 long running east: `A` with two lanes, `B` with one and `C` with two again,
+    /// and `D` with one lane branching off north where `B` ends
+    struct Lanes {
+        graph: RoadGraph,
+        edges: HashMap<Entity, RoadEdge>,
+        occupancy: LaneOccupancy,
+    }
+
+    impl Lanes {
+        fn new() -> Self {
+            let node = |index: u32| Entity::from_raw(100 + index);
+            let links = [
+                (A, (0, 1), Vec3::ZERO, Vec3::X * 10.0, 2),
+                (B, (1, 2), Vec3::X * 10.0, Vec3::X * 20.0, 1),
+                (C, (2, 3), Vec3::X * 20.0, Vec3::X * 30.0, 2),
+                (D, (2, 4), Vec3::X * 20.0, Vec3::new(20.0, 0.0, -10.0), 1),
+            ];
+
+            let mut lanes = Self {
+                graph: RoadGraph::default(),
+                edges: HashMap::new(),
+                occupancy: LaneOccupancy::default(),
+            };
+            for (entity, (start, end), from, to, count) in links {
+                let nodes = EdgeNodes {
+                    start: node(start),
+                    end: node(end),
+                };
+                let edge = RoadEdge::from_start_end(
+                    Transform::from_translation(from).looking_at(to, Vec3::Y),
+                    to,
+                    RoadType::with_lanes(count),
+                );
+
+                lanes.graph.insert_edge(entity, nodes);
+                lanes.edges.insert(entity, edge);
+            }
+
+            lanes
+        }
+
+        fn ahead(&self, edge: Entity, lane: u8, length: f32, distance: f32) -> Option<Seen> {
+            seen(self.occupancy.ahead(
+                &self.graph,
+                |entity| self.edges.get(&entity),
+                LanePosition { edge, lane, length },
+                distance,
+                Some(AGENT),
+            ))
+        }
+
+        fn ahead_along_route(
+            &self,
+            from: LanePosition,
+            route: &[Entity],
+            junction: Option<(f32, u8)>,
+        ) -> Option<Seen> {
+            let lookahead = Lookahead {
+                route: route.iter().copied(),
+                junction: |_, _, _| junction,
+            };
+
+            seen(self.occupancy.ahead_along_route(
+                &self.graph,
+                |entity| self.edges.get(&entity),
+                from,
+                20.0,
+                Some(AGENT),
+                lookahead,
+            ))
+        }
+
+        fn behind(&self, edge: Entity, lane: u8, length: f32, distance: f32) -> Option<Seen> {
+            seen(self.occupancy.behind(
+                &self.graph,
+                |entity| self.edges.get(&entity),
+                LanePosition { edge, lane, length },
+                distance,
+                Some(AGENT),
+            ))
+        }
+    }
+
+    /// Agent, edge, lane and gap of a sighting
+    type Seen = (Entity, Entity, u8, f32);
+
+    fn seen(sighting: Option<Sighting>) -> Option<Seen> {
+        sighting.map(|sighting| {
+            (
+                sighting.occupant.agent,
+                sighting.edge,
+                sighting.lane,
+                sighting.gap,
+            )
+        })
+    }
+
+    #[test]
+    fn ahead_on_the_same_lane_ignores_the_agent() {
+        let mut lanes = Lanes::new();
+        lanes.occupancy.register(A, 0, AGENT, 1.0, 2.0);
+        lanes.occupancy.register(A, 0, OTHER, 5.0, 6.0);
+        lanes.occupancy.register(A, 1, OTHER, 3.0, 4.0);
+
+        assert_eq!(lanes.ahead(A, 0, 2.0, 5.0), Some((OTHER, A, 0, 3.0)));
+        assert_eq!(lanes.ahead(A, 0, 2.0, 2.0), None);
+        // Occupants overlapping the position are ahead, with a negative gap
+        assert_eq!(lanes.ahead(A, 1, 3.5, 5.0), Some((OTHER, A, 1, -0.5)));
+    }
+
+    #[test]
+    fn ahead_continues_on_the_only_next_edge_in_the_nearest_lane() {
+        let mut lanes = Lanes::new();
+        lanes.occupancy.register(B, 0, OTHER, 3.0, 4.0);
+
+        // `B` only has lane 0 for lane 1 of `A` to go on in
+        assert_eq!(lanes.ahead(A, 1, 8.0, 10.0), Some((OTHER, B, 0, 5.0)));
+        assert_eq!(lanes.ahead(A, 1, 8.0, 4.0), None);
+
+        // Past `B` the road forks, so without a route the search stops there
+        lanes.occupancy.register(C, 0, OTHER, 1.0, 2.0);
+        assert_eq!(lanes.ahead(B, 0, 5.0, 20.0), None);
+    }
+
+    #[test]
+    fn ahead_along_route_starts_next_edges_behind_the_junction_path() {
+        let mut lanes = Lanes::new();
+        let from = LanePosition {
+            edge: B,
+            lane: 0,
+            length: 8.0,
+        };
+        // On the path through the junction into lane 1 of `C`, and on lane 0 past it
+        lanes.occupancy.register(C, 1, OTHER, -1.5, -0.5);
+        lanes.occupancy.register(C, 0, OTHER, 1.0, 2.0);
+
+        // 2 to the end of `B`, then 0.5 along the 2 long path before `C`
+        assert_eq!(
+            lanes.ahead_along_route(from, &[C], Some((2.0, 1))),
+            Some((OTHER, C, 1, 2.5))
+        );
+        // Without a path, the lane keeps its index
+        assert_eq!(
+            lanes.ahead_along_route(from, &[C], None),
+            Some((OTHER, C, 0, 3.0))
+        );
+        assert_eq!(lanes.ahead_along_route(from, &[D], None), None);
+    }
+
+    #[test]
+    fn behind_continues_on_the_only_previous_edge_in_the_nearest_lane() {
+        let mut lanes = Lanes::new();
+        lanes.occupancy.register(B, 0, OTHER, 6.0, 7.0);
+        lanes.occupancy.register(C, 1, AGENT, 1.0, 3.0);
+
+        assert_eq!(lanes.behind(C, 1, 2.0, 10.0), Some((OTHER, B, 0, 5.0)));
+        assert_eq!(lanes.behind(C, 1, 2.0, 4.0), None);
+
+        // Lane 0 of `B` goes back to lane 0 of `A`, not lane 1
+        lanes.occupancy.register(A, 1, OTHER, 2.0, 3.0);
+        assert_eq!(lanes.behind(B, 0, 5.0, 30.0), None);
+        lanes.occupancy.register(A, 0, OTHER, 2.0, 3.0);
+        assert_eq!(lanes.behind(B, 0, 5.0, 30.0), Some((OTHER, A, 0, 12.0)));
+    }
+}
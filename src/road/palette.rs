@@ -0,0 +1,310 @@
+use bevy::{ecs::system::SystemParam, input::common_conditions::input_just_released, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::states::GameState;
+
+use super::{
+    cross_section::{CrossSection, DriveSide, LaneKind, LaneProfile},
+    edge::RoadEdge,
+    history::BuildHistory,
+    ROAD_WIDTH,
+};
+
+/// Speed limit of roads that don't set one, in units per second. Fast enough for every vehicle
+/// type to drive at its desired speed.
+pub const DEFAULT_SPEED_LIMIT: f32 = 5.0;
+
+/// Keys that pick the road types of the palette, in order
+const PALETTE_KEYS: [KeyCode; 5] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+];
+
+pub struct RoadPalettePlugin;
+impl Plugin for RoadPalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RoadPalette>().add_systems(
+            Update,
+            (
+                pick_road_type,
+                switch_drive_side.run_if(input_just_released(KeyCode::KeyH)),
+            )
+                .run_if(in_state(GameState::Building)),
+        );
+    }
+}
+
+/// Kind of road, carried by every edge built with it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoadType {
+    /// Lanes across the road, in both directions
+    pub cross_section: CrossSection,
+    /// Fastest any vehicle drives on the road, in units per second
+    pub speed_limit: f32,
+    pub texture: RoadTexture,
+}
+
+impl Default for RoadType {
+    fn default() -> Self {
+        Self {
+            cross_section: CrossSection::uniform(4, ROAD_WIDTH),
+            speed_limit: DEFAULT_SPEED_LIMIT,
+            texture: RoadTexture::Asphalt,
+        }
+    }
+}
+
+impl RoadType {
+    /// Default road with `lanes` lanes, for networks that only know the lane count
+    pub fn with_lanes(lanes: u8) -> Self {
+        Self {
+            cross_section: CrossSection::uniform(lanes, ROAD_WIDTH),
+            ..default()
+        }
+    }
+
+    /// The same road driven the other way, with its cross-section mirrored
+    pub fn reversed(&self) -> Self {
+        Self {
+            cross_section: self.cross_section.reversed(),
+            ..self.clone()
+        }
+    }
+
+    /// The same road for traffic on the other side, with its cross-section flipped
+    pub fn mirrored(&self) -> Self {
+        Self {
+            cross_section: self.cross_section.mirrored(),
+            ..self.clone()
+        }
+    }
+
+    /// The road laid out for traffic on `side`, from a layout for right-hand traffic
+    pub fn for_side(&self, side: DriveSide) -> Self {
+        match side {
+            DriveSide::Right => self.clone(),
+            DriveSide::Left => self.mirrored(),
+        }
+    }
+
+    /// The stretch of the road from `from` to `to`, in fractions of the edge's length
+    pub fn between(&self, from: f32, to: f32) -> Self {
+        Self {
+            cross_section: self.cross_section.between(from, to),
+            ..self.clone()
+        }
+    }
+
+    /// Road that adds or drops lanes to go from the end of this road to the start of `to`,
+    /// with the speed limit and texture of `to`
+    pub fn taper_to(&self, to: &RoadType, side: DriveSide) -> Self {
+        Self {
+            cross_section: self.cross_section.taper_to(&to.cross_section, side),
+            ..to.clone()
+        }
+    }
+
+    /// Number of lanes vehicles drive on along the road
+    pub fn lanes(&self) -> u8 {
+        self.cross_section.driving_lanes()
+    }
+
+    /// Two-way roads are built with an edge in each direction
+    pub fn is_two_way(&self) -> bool {
+        self.cross_section.backward_lanes() > 0
+    }
+
+    /// Width of the whole cross-section where it is widest
+    pub fn width(&self) -> f32 {
+        self.cross_section.width()
+    }
+}
+
+/// Surface the world shader draws a road with
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoadTexture {
+    #[default]
+    Asphalt,
+    Concrete,
+    Cobblestone,
+    Gravel,
+}
+
+impl RoadTexture {
+    /// Index the world shader knows the texture by
+    pub fn index(self) -> u32 {
+        self as u32
+    }
+}
+
+/// Road types to build with, picked with the number keys while building. Their cross-sections
+/// are laid out for right-hand traffic and mirrored for left-hand traffic.
+#[derive(Resource, Debug, Clone)]
+pub struct RoadPalette {
+    pub entries: Vec<PaletteEntry>,
+    pub active: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct PaletteEntry {
+    pub name: String,
+    pub road_type: RoadType,
+}
+
+impl Default for RoadPalette {
+    fn default() -> Self {
+        let entry = |name: &str, road_type| PaletteEntry {
+            name: name.to_string(),
+            road_type,
+        };
+        let lane = LaneProfile::new;
+        let driving = |lanes: usize, width: f32| vec![lane(LaneKind::Driving, width); lanes];
+        let backward =
+            |lanes: usize, width: f32| vec![lane(LaneKind::Driving, width).backward(); lanes];
+
+        Self {
+            entries: vec![
+                entry(
+                    "Avenue",
+                    RoadType {
+                        cross_section: CrossSection {
+                            lanes: [
+                                vec![lane(LaneKind::Sidewalk, 0.6)],
+                                driving(4, ROAD_WIDTH),
+                                vec![lane(LaneKind::Bike, 0.5), lane(LaneKind::Sidewalk, 0.6)],
+                            ]
+                            .concat(),
+                        },
+                        ..default()
+                    },
+                ),
+                entry(
+                    "Street",
+                    RoadType {
+                        cross_section: CrossSection {
+                            lanes: [
+                                vec![lane(LaneKind::Sidewalk, 0.6)],
+                                backward(1, ROAD_WIDTH),
+                                driving(1, ROAD_WIDTH),
+                                vec![lane(LaneKind::Sidewalk, 0.6)],
+                            ]
+                            .concat(),
+                        },
+                        speed_limit: 3.0,
+                        ..default()
+                    },
+                ),
+                entry(
+                    "Main road",
+                    RoadType {
+                        cross_section: CrossSection {
+                            lanes: [
+                                vec![lane(LaneKind::Sidewalk, 0.6), lane(LaneKind::Parking, 0.8)],
+                                backward(1, ROAD_WIDTH),
+                                driving(2, ROAD_WIDTH),
+                                vec![lane(LaneKind::Parking, 0.8), lane(LaneKind::Sidewalk, 0.6)],
+                            ]
+                            .concat(),
+                        },
+                        speed_limit: 4.0,
+                        ..default()
+                    },
+                ),
+                entry(
+                    "Highway",
+                    RoadType {
+                        cross_section: CrossSection {
+                            lanes: [
+                                vec![lane(LaneKind::Shoulder, 0.6)],
+                                backward(3, 1.2 * ROAD_WIDTH),
+                                vec![lane(LaneKind::Median, 0.8)],
+                                driving(3, 1.2 * ROAD_WIDTH),
+                                vec![lane(LaneKind::Shoulder, 0.6)],
+                            ]
+                            .concat(),
+                        },
+                        speed_limit: 8.0,
+                        texture: RoadTexture::Concrete,
+                    },
+                ),
+                entry(
+                    "Alley",
+                    RoadType {
+                        cross_section: CrossSection::uniform(1, 0.8 * ROAD_WIDTH),
+                        speed_limit: 2.0,
+                        texture: RoadTexture::Cobblestone,
+                    },
+                ),
+            ],
+            active: 0,
+        }
+    }
+}
+
+impl RoadPalette {
+    /// Road type new roads are built with when traffic keeps to `side`
+    pub fn active(&self, side: DriveSide) -> RoadType {
+        self.entries
+            .get(self.active)
+            .map(|entry| entry.road_type.for_side(side))
+            .unwrap_or_default()
+    }
+}
+
+/// Road type new roads are built with, laid out for the side traffic keeps to
+#[derive(SystemParam)]
+pub struct ActiveRoadType<'w> {
+    palette: Res<'w, RoadPalette>,
+    drive_side: Res<'w, DriveSide>,
+}
+
+impl ActiveRoadType<'_> {
+    pub fn get(&self) -> RoadType {
+        self.palette.active(*self.drive_side)
+    }
+
+    pub fn drive_side(&self) -> DriveSide {
+        *self.drive_side
+    }
+}
+
+fn pick_road_type(input: Res<ButtonInput<KeyCode>>, mut palette: ResMut<RoadPalette>) {
+    let Some(index) = PALETTE_KEYS
+        .iter()
+        .take(palette.entries.len())
+        .position(|key| input.just_released(*key))
+    else {
+        return;
+    };
+
+    palette.active = index;
+    info!("Building {} roads", palette.entries[index].name);
+}
+
+/// Makes traffic keep to the other side with H. Every edge is mirrored to match, so the
+/// history from before the switch no longer fits and is forgotten.
+fn switch_drive_side(
+    mut drive_side: ResMut<DriveSide>,
+    mut edges: Query<&mut RoadEdge>,
+    mut history: ResMut<BuildHistory>,
+) {
+    *drive_side = drive_side.other();
+
+    for mut edge in &mut edges {
+        *edge = RoadEdge::new(
+            edge.start(),
+            edge.end(),
+            edge.center(),
+            edge.radius(),
+            edge.length(),
+            edge.twist(),
+            edge.road_type().mirrored(),
+        );
+    }
+
+    history.clear();
+    info!("Traffic keeps to the {:?}", *drive_side);
+}
@@ -1,17 +1,34 @@
 use bevy::{
     input::{common_conditions::input_just_released, mouse::MouseMotion},
     prelude::*,
+    utils::{HashMap, HashSet},
 };
 
 use crate::{raycast::Raycast, states::GameState};
 
 use super::{
+    asset::inherit_network_asset,
     biarc,
-    edge::{RoadEdge, Twist},
+    cross_section::DriveSide,
+    edge::RoadEdge,
+    edit::handle_grabbed,
+    graph::{EdgeNodes, RoadGraph},
+    history::BuildHistory,
+    node::RoadNode,
+    palette::{ActiveRoadType, RoadType},
     world::WorldTile,
-    RoadSpawner,
+    RoadSpawner, ROAD_WIDTH,
 };
 
+/// Crossings closer than this to the end of an edge join that end instead of splitting the edge
+const JUNCTION_MARGIN: f32 = 0.5 * ROAD_WIDTH;
+
+/// Edge ends closer than this to a node are connected to it
+const NODE_SNAP: f32 = 0.01;
+
+/// Length over which a road going on from a road of another type adds or drops lanes
+const TAPER_LENGTH: f32 = 6.0 * ROAD_WIDTH;
+
 pub struct PlaceholderPlugin;
 impl Plugin for PlaceholderPlugin {
     fn build(&self, app: &mut App) {
@@ -19,7 +36,9 @@ impl Plugin for PlaceholderPlugin {
             Update,
             (
                 (
-                    start_building.run_if(input_just_released(MouseButton::Left)),
+                    start_building
+                        .run_if(input_just_released(MouseButton::Left))
+                        .run_if(not(handle_grabbed)),
                     snip_road.run_if(input_just_released(MouseButton::Right)),
                 )
                     .in_set(BuildSystemSet::NotBuilding),
@@ -45,11 +64,23 @@ pub enum BuildSystemSet {
     ExitBuildMode,
     Building,
     NotBuilding,
+    Bulldozing,
+}
+
+/// What clicking in build mode does
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BuildTool {
+    /// Left click on a spawner or node starts a road, right click snips one
+    #[default]
+    Road,
+    /// Left click deletes the edge under the cursor, dragging deletes all edges in a rectangle
+    Bulldoze,
 }
 
 fn start_building(
     raycast: Raycast<With<RoadSpawner>>,
     node_query: Query<&GlobalTransform>,
+    road_type: ActiveRoadType,
     mut commands: Commands,
 ) {
     let Some((entity, hitpoint)) = raycast.cursor_ray() else {
@@ -61,7 +92,7 @@ fn start_building(
 
     commands.spawn((
         Name::new("RoadPlaceholder"),
-        RoadEdge::from_start_end(Transform::from(*start), hitpoint, 4),
+        RoadEdge::from_start_end(Transform::from(*start), hitpoint, road_type.get()),
         RoadPlaceholder,
     ));
 }
@@ -76,6 +107,7 @@ fn move_road_placeholder(
     mut placeholders: Query<(Entity, &mut RoadEdge), With<RoadPlaceholder>>,
     mut commands: Commands,
     input: Res<ButtonInput<KeyCode>>,
+    road_type: ActiveRoadType,
 ) {
     let Some((tile_entity, hitpoint)) = world_cast.cursor_ray() else {
         return;
@@ -99,25 +131,28 @@ fn move_road_placeholder(
             continue;
         }
 
-        let lane = match edge.twist() {
-            Twist::Straight => match edge.start().left().dot(hitpoint).is_sign_negative() {
-                true => edge.lanes() as i32,
-                false => -1,
-            },
-            _ => match edge.radius().powi(2) < (hitpoint - edge.center()).length_squared() {
-                true => edge.lanes() as i32,
-                false => -1,
-            },
+        // Join the side of the road that was hit, just outside its outer lane
+        let length = edge.coord_to_length(hitpoint);
+        let on_edge = edge.interpolate(length);
+        let from_left = match on_edge
+            .left()
+            .dot(hitpoint - on_edge.translation)
+            .is_sign_negative()
+        {
+            true => edge.lanes() as f32,
+            false => -1.0,
         };
+        let drive_side = road_type.drive_side();
+        let lane = drive_side.from_left(from_left, edge.lanes()) as i32;
 
-        let hit_transform = edge.interpolate_lane(edge.coord_to_length(hitpoint), lane);
+        let hit_transform = edge.interpolate_lane(length, lane, drive_side);
 
         let mut placeholder_iter = placeholders.iter_mut();
         let (_, mut first_edge_placeholder) = placeholder_iter.next().unwrap();
         let (biarc_first_edge, biarc_last_edge) = biarc::compute_biarc(
             first_edge_placeholder.start(),
             hit_transform,
-            first_edge_placeholder.lanes(),
+            road_type.get(),
         );
 
         let Some((_, mut placeholder_last_edge)) = placeholder_iter.next() else {
@@ -148,15 +183,98 @@ fn move_road_placeholder(
         false => (hitpoint * 4.0).floor() * 0.25,
     };
 
-    *edge = RoadEdge::from_start_end(edge.start(), hitpoint, edge.lanes());
+    *edge = RoadEdge::from_start_end(edge.start(), hitpoint, road_type.get());
 
     if let Some((entity, _)) = placeholder_iter.next() {
         commands.entity(entity).despawn_recursive();
     }
 }
 
-fn finalize_road(mut commands: Commands, query: Query<(Entity, &RoadEdge), With<RoadPlaceholder>>) {
-    let (_, edge) = query.iter().last().unwrap();
+fn finalize_road(
+    mut commands: Commands,
+    query: Query<(Entity, &RoadEdge), With<RoadPlaceholder>>,
+    edges: Query<(Entity, &RoadEdge, &EdgeNodes), Without<RoadPlaceholder>>,
+    nodes: Query<(Entity, &Transform), With<RoadNode>>,
+    graph: Res<RoadGraph>,
+    mut history: ResMut<BuildHistory>,
+    drive_side: Res<DriveSide>,
+) {
+    history.record(BuildHistory::snapshot(&graph, |entity| {
+        edges.get(entity).ok().map(|(_, edge, _)| edge)
+    }));
+
+    let mut placeholders = query.iter().collect::<Vec<(Entity, &RoadEdge)>>();
+
+    // Order the placeholders from the start of the road to its end
+    if let [(_, first), (_, second)] = placeholders.as_slice() {
+        if second.end().translation.distance(first.start().translation) < NODE_SNAP {
+            placeholders.swap(0, 1);
+        }
+    }
+
+    let (_, edge) = placeholders.last().unwrap();
+    let end = edge.end();
+    let road_type = edge.road_type().clone();
+
+    let mut pieces = placeholders
+        .iter()
+        .map(|(entity, edge)| (*entity, (*edge).clone()))
+        .collect::<Vec<(Entity, RoadEdge)>>();
+
+    // Going on from a road of another type, the first stretch adds or drops lanes
+    let start = pieces[0].1.start();
+    let incoming = edges.iter().find(|(_, edge, _)| {
+        edge.end().translation.distance(start.translation) < NODE_SNAP
+            && edge.end().forward().dot(*start.forward()) > 1.0 - 1e-3
+    });
+    if let Some((_, incoming, _)) = incoming {
+        let from = incoming.road_type();
+        if from.cross_section.at(1.0) != pieces[0].1.road_type().cross_section.at(0.0) {
+            match taper(from, &pieces[0].1, *drive_side) {
+                (taper, Some(rest)) => {
+                    pieces[0].1 = rest;
+                    pieces.insert(0, (commands.spawn(Name::new("Road Edge")).id(), taper));
+                }
+                (taper, None) => pieces[0].1 = taper,
+            }
+        }
+    }
+
+    let mut new_edges = HashMap::new();
+    let mut previous = find_or_spawn_node(&mut commands, &nodes, start);
+    for (entity, edge) in pieces {
+        let node = find_or_spawn_node(&mut commands, &nodes, edge.end());
+
+        // Two-way roads get an edge back along the same centerline for their backward lanes
+        if edge.road_type().is_two_way() {
+            let twin = commands.spawn(Name::new("Road Edge")).id();
+            new_edges.insert(
+                twin,
+                (
+                    edge.reversed(),
+                    EdgeNodes {
+                        start: node,
+                        end: previous,
+                    },
+                ),
+            );
+        }
+
+        new_edges.insert(
+            entity,
+            (
+                edge,
+                EdgeNodes {
+                    start: previous,
+                    end: node,
+                },
+            ),
+        );
+
+        previous = node;
+    }
+
+    create_junctions(&mut commands, new_edges, &edges, end.translation);
 
     for (entity, _) in query.iter() {
         commands.entity(entity).remove::<RoadPlaceholder>();
@@ -166,14 +284,191 @@ fn finalize_road(mut commands: Commands, query: Query<(Entity, &RoadEdge), With<
     commands.spawn((
         Name::new("RoadPlaceholder"),
         RoadEdge::from_start_end(
-            edge.end(),
-            edge.end().translation + *edge.end().forward() * 0.01 + *edge.end().left() * 0.01,
-            edge.lanes(),
+            end,
+            end.translation + *end.forward() * 0.01 + *end.left() * 0.01,
+            road_type,
         ),
         RoadPlaceholder,
     ));
 }
 
+/// Turns the start of `edge` into a taper from the end of a road of type `from`, over
+/// `TAPER_LENGTH` or the whole edge when it is not much longer. Returns the taper and what is
+/// left of the edge.
+fn taper(from: &RoadType, edge: &RoadEdge, side: DriveSide) -> (RoadEdge, Option<RoadEdge>) {
+    let road_type = from.taper_to(edge.road_type(), side);
+    if edge.length() < TAPER_LENGTH + JUNCTION_MARGIN {
+        let taper = RoadEdge::from_start_end(edge.start(), edge.end().translation, road_type);
+        return (taper, None);
+    }
+
+    let (first, rest) = edge.split(TAPER_LENGTH);
+    let taper = RoadEdge::from_start_end(first.start(), first.end().translation, road_type);
+    (taper, Some(rest))
+}
+
+fn find_or_spawn_node(
+    commands: &mut Commands,
+    nodes: &Query<(Entity, &Transform), With<RoadNode>>,
+    transform: Transform,
+) -> Entity {
+    nodes
+        .iter()
+        .find(|(_, node)| node.translation.distance(transform.translation) < NODE_SNAP)
+        .map(|(entity, _)| entity)
+        .unwrap_or_else(|| spawn_node(commands, transform.translation))
+}
+
+pub fn spawn_node(commands: &mut Commands, position: Vec3) -> Entity {
+    commands
+        .spawn((
+            Name::new("Road Node"),
+            TransformBundle::from_transform(Transform::from_translation(position)),
+            RoadNode,
+        ))
+        .id()
+}
+
+/// Connects the new edges to their nodes. Where they cross or end on existing edges, both are
+/// split and the pieces are joined in a junction node.
+fn create_junctions(
+    commands: &mut Commands,
+    new_edges: HashMap<Entity, (RoadEdge, EdgeNodes)>,
+    edges: &Query<(Entity, &RoadEdge, &EdgeNodes), Without<RoadPlaceholder>>,
+    road_end: Vec3,
+) {
+    let mut junctions: Vec<(Entity, Vec3)> = Vec::new();
+    let mut cuts: HashMap<Entity, Vec<(f32, Entity)>> = new_edges
+        .keys()
+        .map(|entity| (*entity, Vec::new()))
+        .collect();
+
+    for (new_entity, (new_edge, _)) in new_edges.iter() {
+        for (entity, edge, nodes) in edges.iter() {
+            let Some(intersection) = new_edge.intersects_edge(edge) else {
+                continue;
+            };
+
+            let crossings = match intersection.points.is_empty() {
+                false => intersection.points,
+                // Only the end of the new road may join an existing road from the side, which
+                // is the start of the edges going back on two-way roads
+                true => match [new_edge.start(), new_edge.end()]
+                    .iter()
+                    .any(|end| end.translation.distance(road_end) < JUNCTION_MARGIN)
+                {
+                    true => {
+                        let length = edge.centerline().project(road_end.xz());
+                        vec![edge.interpolate(length).translation]
+                    }
+                    false => continue,
+                },
+            };
+
+            for crossing in crossings {
+                let length = edge.centerline().project(crossing.xz());
+
+                // Crossings close to an end of the existing edge join its node instead of
+                // cutting it
+                let (end_node, crossing) = if length < JUNCTION_MARGIN {
+                    (Some(nodes.start), edge.start().translation)
+                } else if length > edge.length() - JUNCTION_MARGIN {
+                    (Some(nodes.end), edge.end().translation)
+                } else {
+                    (None, crossing)
+                };
+
+                let junction = match junctions
+                    .iter()
+                    .find(|(_, junction)| junction.distance(crossing) < JUNCTION_MARGIN)
+                {
+                    Some((junction, _)) => *junction,
+                    None => {
+                        let junction = end_node.unwrap_or_else(|| {
+                            let junction = spawn_node(commands, crossing);
+                            commands.entity(junction).insert(Name::new("Junction"));
+                            junction
+                        });
+                        junctions.push((junction, crossing));
+                        junction
+                    }
+                };
+
+                if end_node.is_none() {
+                    cuts.entry(entity).or_default().push((length, junction));
+                }
+
+                let new_length = new_edge.centerline().project(crossing.xz());
+                cuts.entry(*new_entity)
+                    .or_default()
+                    .push((new_length, junction));
+            }
+        }
+    }
+
+    // Nodes of the new edges that a junction took the place of
+    let mut replaced = HashSet::new();
+    let mut used = edges
+        .iter()
+        .filter(|(entity, _, _)| !cuts.contains_key(entity))
+        .flat_map(|(_, _, nodes)| [nodes.start, nodes.end])
+        .collect::<HashSet<Entity>>();
+
+    for (entity, mut edge_cuts) in cuts {
+        let (edge, nodes) = match new_edges.get(&entity) {
+            Some((edge, nodes)) => (edge, *nodes),
+            None => {
+                let (_, edge, nodes) = edges.get(entity).unwrap();
+                (edge, *nodes)
+            }
+        };
+
+        edge_cuts.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        edge_cuts.dedup_by_key(|(_, junction)| *junction);
+
+        let mut remaining = edge.clone();
+        let mut offset = 0.0;
+        let mut piece = entity;
+        let mut start = nodes.start;
+        let mut end = nodes.end;
+
+        for (length, junction) in edge_cuts {
+            if length - offset > JUNCTION_MARGIN && edge.length() - length > JUNCTION_MARGIN {
+                let (first, second) = remaining.split(length - offset);
+                commands.entity(piece).insert((
+                    first,
+                    EdgeNodes {
+                        start,
+                        end: junction,
+                    },
+                ));
+                used.extend([start, junction]);
+
+                piece = commands.spawn(Name::new("Road Edge")).id();
+                inherit_network_asset(commands, entity, piece);
+                remaining = second;
+                offset = length;
+                start = junction;
+            } else if length - offset <= JUNCTION_MARGIN {
+                replaced.insert(start);
+                start = junction;
+            } else {
+                replaced.insert(end);
+                end = junction;
+            }
+        }
+
+        used.extend([start, end]);
+        commands
+            .entity(piece)
+            .insert((remaining, EdgeNodes { start, end }));
+    }
+
+    for node in replaced.difference(&used) {
+        commands.entity(*node).despawn_recursive();
+    }
+}
+
 fn remove_placeholders(mut commands: Commands, query: Query<Entity, With<RoadPlaceholder>>) {
     for entity in query.into_iter() {
         commands.entity(entity).despawn_recursive();
@@ -195,37 +490,44 @@ fn show_nodes(mut query: Query<&mut Visibility, With<RoadSpawner>>) {
 fn snip_road(
     world_cast: Raycast<With<WorldTile>>,
     world_tiles: Query<&WorldTile>,
-    mut edges: Query<&mut RoadEdge>,
+    mut edges: Query<(&mut RoadEdge, &mut EdgeNodes)>,
+    graph: Res<RoadGraph>,
+    mut history: ResMut<BuildHistory>,
     mut commands: Commands,
 ) {
     let Some((tile_entity, hitpoint)) = world_cast.cursor_ray() else {
         return;
     };
 
+    let mut before = Some(BuildHistory::snapshot(&graph, |entity| {
+        edges.get(entity).ok().map(|(edge, _)| edge)
+    }));
+
     for edge_entity in &world_tiles.get(tile_entity).unwrap().edges {
-        let mut edge = edges.get_mut(*edge_entity).unwrap();
+        let (mut edge, mut nodes) = edges.get_mut(*edge_entity).unwrap();
 
         if !edge.intersects_point(hitpoint) {
             continue;
         }
 
-        let end = edge.end();
-        let length_first_half = edge.coord_to_length(hitpoint);
-        let length_second_half = edge.length() - length_first_half;
+        if let Some(before) = before.take() {
+            history.record(before);
+        }
 
-        edge.resize(length_first_half);
+        let (first_half, second_half) = edge.split(edge.coord_to_length(hitpoint));
+        let node = spawn_node(&mut commands, first_half.end().translation);
+        let second_nodes = EdgeNodes {
+            start: node,
+            end: nodes.end,
+        };
 
-        let second_half = RoadEdge::new(
-            edge.end(),
-            end,
-            edge.center(),
-            edge.radius(),
-            length_second_half,
-            edge.twist(),
-            edge.lanes(),
-            edge.aabb3(),
-        );
+        *edge = first_half;
+        nodes.end = node;
 
-        commands.spawn((Name::new("RoadEdge"), second_half));
+        let second = commands
+            .spawn((Name::new("RoadEdge"), second_half, second_nodes))
+            .id();
+        inherit_network_asset(&mut commands, *edge_entity, second);
+        inherit_network_asset(&mut commands, *edge_entity, node);
     }
 }
@@ -0,0 +1,471 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{edge::RoadEdge, graph::RoadGraph};
+
+/// Position on the network, `length` along `edge`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoutePoint {
+    pub edge: Entity,
+    pub length: f32,
+}
+
+impl RoutePoint {
+    pub fn new(edge: Entity, length: f32) -> Self {
+        Self { edge, length }
+    }
+
+    /// The point on any of `edges` closest to `position`
+    pub fn nearest<'a>(
+        position: Vec3,
+        edges: impl IntoIterator<Item = (Entity, &'a RoadEdge)>,
+    ) -> Option<Self> {
+        edges
+            .into_iter()
+            .map(|(entity, edge)| {
+                let centerline = edge.centerline();
+                let distance = centerline.distance_to_point(position.xz());
+                (
+                    distance,
+                    Self::new(entity, centerline.project(position.xz())),
+                )
+            })
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, point)| point)
+    }
+}
+
+/// Stretch of a single edge driven on a route
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RouteLeg {
+    pub edge: Entity,
+    pub from: f32,
+    pub to: f32,
+}
+
+impl RouteLeg {
+    pub fn distance(&self) -> f32 {
+        self.to - self.from
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Route {
+    pub legs: Vec<RouteLeg>,
+    /// Total cost according to the cost function the route was planned with
+    pub cost: f32,
+}
+
+impl Route {
+    pub fn distance(&self) -> f32 {
+        self.legs.iter().map(RouteLeg::distance).sum()
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.legs.iter().map(|leg| leg.edge)
+    }
+}
+
+/// Cost function used to weigh edges when planning a route
+pub trait RouteCost {
+    /// Cost of driving `distance` along `edge`
+    fn cost(&self, entity: Entity, edge: &RoadEdge, distance: f32) -> f32;
+
+    /// Lower bound of the cost to cover `distance` in a straight line. Guides the search, so it
+    /// must never overestimate.
+    fn estimate(&self, _distance: f32) -> f32 {
+        0.0
+    }
+}
+
+/// Plain cost functions search without a heuristic
+impl<F: Fn(Entity, &RoadEdge, f32) -> f32> RouteCost for F {
+    fn cost(&self, entity: Entity, edge: &RoadEdge, distance: f32) -> f32 {
+        self(entity, edge, distance)
+    }
+}
+
+/// Shortest route by distance, the default
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Distance;
+
+impl RouteCost for Distance {
+    fn cost(&self, _entity: Entity, _edge: &RoadEdge, distance: f32) -> f32 {
+        distance
+    }
+
+    fn estimate(&self, distance: f32) -> f32 {
+        distance
+    }
+}
+
+/// Fastest route, driving every edge at its speed limit
+#[derive(Debug, Clone, Default)]
+pub struct TravelTime {
+    /// Speeds driven instead of the speed limit of some edges, e.g. measured ones
+    pub speeds: HashMap<Entity, f32>,
+    /// Highest speed limit on the network. Bounds the estimate, so it must not be lower than
+    /// the speed limit of any edge.
+    pub max_speed: f32,
+}
+
+impl TravelTime {
+    pub fn new(max_speed: f32) -> Self {
+        Self {
+            speeds: HashMap::new(),
+            max_speed,
+        }
+    }
+
+    /// Travel time on the network made up of `edges`
+    pub fn for_edges<'a>(edges: impl IntoIterator<Item = &'a RoadEdge>) -> Self {
+        Self::new(
+            edges
+                .into_iter()
+                .map(|edge| edge.road_type().speed_limit)
+                .fold(0.0, f32::max),
+        )
+    }
+
+    fn speed(&self, entity: Entity, edge: &RoadEdge) -> f32 {
+        self.speeds
+            .get(&entity)
+            .copied()
+            .unwrap_or(edge.road_type().speed_limit)
+    }
+
+    fn max_speed(&self) -> f32 {
+        self.speeds.values().copied().fold(self.max_speed, f32::max)
+    }
+}
+
+impl RouteCost for TravelTime {
+    fn cost(&self, entity: Entity, edge: &RoadEdge, distance: f32) -> f32 {
+        distance / self.speed(entity, edge)
+    }
+
+    fn estimate(&self, distance: f32) -> f32 {
+        match self.max_speed() > 0.0 {
+            true => distance / self.max_speed(),
+            false => 0.0,
+        }
+    }
+}
+
+/// Scales the cost of busy edges. Factors should be at least 1 to keep the estimate of the
+/// inner cost valid.
+#[derive(Debug, Clone, Default)]
+pub struct Congestion<C: RouteCost> {
+    pub inner: C,
+    pub factors: HashMap<Entity, f32>,
+}
+
+impl<C: RouteCost> Congestion<C> {
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            factors: HashMap::new(),
+        }
+    }
+}
+
+impl<C: RouteCost> RouteCost for Congestion<C> {
+    fn cost(&self, entity: Entity, edge: &RoadEdge, distance: f32) -> f32 {
+        self.inner.cost(entity, edge, distance) * self.factors.get(&entity).copied().unwrap_or(1.0)
+    }
+
+    fn estimate(&self, distance: f32) -> f32 {
+        self.inner.estimate(distance)
+    }
+}
+
+/// Entry of the open set. `edge` is `None` once the destination itself is reached.
+struct Candidate {
+    estimate: f32,
+    cost: f32,
+    edge: Option<Entity>,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    // Reversed to turn the max-heap into a min-heap
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+/// Plans the cheapest route from `from` to `to` with A*. Edges are driven from start to end.
+/// `edges` looks up the geometry of an edge, e.g. `|entity| query.get(entity).ok()`.
+pub fn find_route<'a>(
+    graph: &RoadGraph,
+    edges: impl Fn(Entity) -> Option<&'a RoadEdge>,
+    from: RoutePoint,
+    to: RoutePoint,
+    cost: &impl RouteCost,
+) -> Option<Route> {
+    let from_edge = edges(from.edge)?;
+    let to_edge = edges(to.edge)?;
+
+    if from.edge == to.edge && from.length <= to.length {
+        return Some(Route {
+            legs: vec![RouteLeg {
+                edge: from.edge,
+                from: from.length,
+                to: to.length,
+            }],
+            cost: cost.cost(from.edge, from_edge, to.length - from.length),
+        });
+    }
+
+    let destination = to_edge.interpolate(to.length).translation;
+
+    // Cheapest known cost to reach the start of an edge, and the edge it was reached from
+    let mut best: HashMap<Entity, f32> = HashMap::new();
+    let mut came_from: HashMap<Entity, Entity> = HashMap::new();
+    let mut open = BinaryHeap::new();
+
+    let initial_cost = cost.cost(from.edge, from_edge, from_edge.length() - from.length);
+    for next in graph.next_edges(from.edge) {
+        let Some(next_edge) = edges(*next) else {
+            continue;
+        };
+
+        best.insert(*next, initial_cost);
+        came_from.insert(*next, from.edge);
+        open.push(Candidate {
+            estimate: initial_cost
+                + cost.estimate(next_edge.start().translation.distance(destination)),
+            cost: initial_cost,
+            edge: Some(*next),
+        });
+    }
+
+    let mut total_cost = None;
+    while let Some(candidate) = open.pop() {
+        let Some(entity) = candidate.edge else {
+            total_cost = Some(candidate.cost);
+            break;
+        };
+
+        if best.get(&entity).is_some_and(|cost| candidate.cost > *cost) {
+            continue;
+        }
+
+        let Some(edge) = edges(entity) else {
+            continue;
+        };
+
+        if entity == to.edge {
+            let arrival = candidate.cost + cost.cost(entity, edge, to.length);
+            open.push(Candidate {
+                estimate: arrival,
+                cost: arrival,
+                edge: None,
+            });
+        }
+
+        let through = candidate.cost + cost.cost(entity, edge, edge.length());
+        for next in graph.next_edges(entity) {
+            if best.get(next).is_some_and(|cost| through >= *cost) {
+                continue;
+            }
+
+            let Some(next_edge) = edges(*next) else {
+                continue;
+            };
+
+            best.insert(*next, through);
+            came_from.insert(*next, entity);
+            open.push(Candidate {
+                estimate: through
+                    + cost.estimate(next_edge.start().translation.distance(destination)),
+                cost: through,
+                edge: Some(*next),
+            });
+        }
+    }
+
+    let total_cost = total_cost?;
+
+    let mut legs = vec![RouteLeg {
+        edge: to.edge,
+        from: 0.0,
+        to: to.length,
+    }];
+
+    let mut current = to.edge;
+    loop {
+        let previous = came_from[&current];
+        if previous == from.edge {
+            break;
+        }
+
+        legs.push(RouteLeg {
+            edge: previous,
+            from: 0.0,
+            to: edges(previous)?.length(),
+        });
+        current = previous;
+    }
+
+    legs.push(RouteLeg {
+        edge: from.edge,
+        from: from.length,
+        to: from_edge.length(),
+    });
+    legs.reverse();
+
+    Some(Route {
+        legs,
+        cost: total_cost,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::road::{graph::EdgeNodes, palette::RoadType};
+
+    /// Nodes on a grid and directed edges between them, by index
+    struct Network {
+        graph: RoadGraph,
+        edges: HashMap<Entity, RoadEdge>,
+        ids: Vec<Entity>,
+    }
+
+    impl Network {
+        fn new(points: &[Vec3], links: &[(usize, usize, f32)]) -> Self {
+            let nodes = (0..points.len() as u32)
+                .map(|index| Entity::from_raw(1000 + index))
+                .collect::<Vec<Entity>>();
+            let mut network = Self {
+                graph: RoadGraph::default(),
+                edges: HashMap::new(),
+                ids: Vec::new(),
+            };
+
+            for (index, (start, end, speed_limit)) in links.iter().enumerate() {
+                let entity = Entity::from_raw(index as u32);
+                let road_type = RoadType {
+                    speed_limit: *speed_limit,
+                    ..RoadType::with_lanes(1)
+                };
+                let edge = RoadEdge::from_start_end(
+                    Transform::from_translation(points[*start]).looking_at(points[*end], Vec3::Y),
+                    points[*end],
+                    road_type,
+                );
+
+                network.graph.insert_edge(
+                    entity,
+                    EdgeNodes {
+                        start: nodes[*start],
+                        end: nodes[*end],
+                    },
+                );
+                network.edges.insert(entity, edge);
+                network.ids.push(entity);
+            }
+
+            network
+        }
+
+        fn route(&self, from: usize, to: usize, cost: &impl RouteCost) -> Option<Vec<Entity>> {
+            find_route(
+                &self.graph,
+                |entity| self.edges.get(&entity),
+                RoutePoint::new(self.ids[from], 0.0),
+                RoutePoint::new(self.ids[to], 1.0),
+                cost,
+            )
+            .map(|route| route.edges().collect())
+        }
+    }
+
+    /// One-way loop round a square, with a long but fast detour through a fifth node off to the
+    /// side
+    fn square() -> Network {
+        Network::new(
+            &[
+                Vec3::ZERO,
+                Vec3::new(10.0, 0.0, 0.0),
+                Vec3::new(10.0, 0.0, 10.0),
+                Vec3::new(0.0, 0.0, 10.0),
+                Vec3::new(30.0, 0.0, 5.0),
+            ],
+            &[
+                (0, 1, 5.0),
+                (1, 2, 5.0),
+                (2, 3, 5.0),
+                (1, 4, 40.0),
+                (4, 2, 40.0),
+                (3, 0, 5.0),
+            ],
+        )
+    }
+
+    #[test]
+    fn shortest_route_by_distance() {
+        let network = square();
+        let ids = &network.ids;
+
+        assert_eq!(
+            network.route(0, 2, &Distance),
+            Some(vec![ids[0], ids[1], ids[2]])
+        );
+    }
+
+    #[test]
+    fn fastest_route_follows_speed_limits() {
+        let network = square();
+        let ids = &network.ids;
+        let cost = TravelTime::for_edges(network.edges.values());
+
+        assert_eq!(
+            network.route(0, 2, &cost),
+            Some(vec![ids[0], ids[3], ids[4], ids[2]])
+        );
+    }
+
+    #[test]
+    fn one_way_edges_are_driven_forward_only() {
+        let network = square();
+        let ids = &network.ids;
+
+        // Getting back onto the edge before takes the whole loop
+        assert_eq!(
+            network.route(2, 1, &Distance),
+            Some(vec![ids[2], ids[5], ids[0], ids[1]])
+        );
+    }
+
+    #[test]
+    fn no_route_between_disconnected_edges() {
+        let network = Network::new(
+            &[
+                Vec3::ZERO,
+                Vec3::new(10.0, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, 10.0),
+                Vec3::new(10.0, 0.0, 10.0),
+            ],
+            &[(0, 1, 5.0), (2, 3, 5.0), (3, 2, 5.0)],
+        );
+
+        assert_eq!(network.route(0, 1, &Distance), None);
+        assert_eq!(network.route(1, 0, &Distance), None);
+    }
+}
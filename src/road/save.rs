@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use bevy::{input::common_conditions::input_just_released, prelude::*};
+
+use crate::interop;
+
+use super::{
+    cross_section::DriveSide, edge::RoadEdge, graph::RoadGraph, history::BuildHistory,
+    network::RoadNetwork, node::RoadNode, placeholder::RoadPlaceholder, RoadSpawner,
+};
+
+pub struct SaveLoadPlugin;
+impl Plugin for SaveLoadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NetworkPath>().add_systems(
+            Update,
+            (
+                save_network.run_if(input_just_released(KeyCode::KeyS)),
+                load_network.run_if(input_just_released(KeyCode::KeyO)),
+            )
+                .run_if(control_pressed),
+        );
+    }
+}
+
+/// File the network is saved to with Ctrl+S and loaded from with Ctrl+O. The extension picks
+/// the format, `.ron`, `.json` or OpenDRIVE `.xodr`. OpenStreetMap `.osm` files can be loaded
+/// too.
+#[derive(Resource, Debug, Clone)]
+pub struct NetworkPath(pub PathBuf);
+
+impl Default for NetworkPath {
+    fn default() -> Self {
+        Self(PathBuf::from("network.road.ron"))
+    }
+}
+
+pub fn control_pressed(input: Res<ButtonInput<KeyCode>>) -> bool {
+    input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+}
+
+fn save_network(
+    path: Res<NetworkPath>,
+    graph: Res<RoadGraph>,
+    edges: Query<&RoadEdge, Without<RoadPlaceholder>>,
+    spawners: Query<&Transform, With<RoadSpawner>>,
+    drive_side: Res<DriveSide>,
+) {
+    let network = RoadNetwork::capture(
+        &graph,
+        |entity| edges.get(entity).ok(),
+        spawners.iter().copied(),
+        *drive_side,
+    );
+
+    match interop::save_network(&network, &path.0) {
+        Ok(()) => info!("Saved road network to {}", path.0.display()),
+        Err(error) => error!("Could not save road network: {error}"),
+    }
+}
+
+/// Replaces the network with the one on disk and keeps traffic to its side. Vehicles go with
+/// the edges they drive on, and the build history is forgotten.
+fn load_network(
+    path: Res<NetworkPath>,
+    edges: Query<Entity, (With<RoadEdge>, Without<RoadPlaceholder>)>,
+    nodes: Query<Entity, With<RoadNode>>,
+    spawners: Query<Entity, With<RoadSpawner>>,
+    mut history: ResMut<BuildHistory>,
+    mut commands: Commands,
+) {
+    let network = match interop::load_network(&path.0).and_then(|network| {
+        network.validate()?;
+        Ok(network)
+    }) {
+        Ok(network) => network,
+        Err(error) => {
+            error!("Could not load road network: {error}");
+            return;
+        }
+    };
+
+    history.clear();
+    for entity in edges.iter().chain(&nodes).chain(&spawners) {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.insert_resource(network.drive_side);
+    if let Err(error) = network.spawn(&mut commands) {
+        error!("Could not load road network: {error}");
+    }
+}
@@ -8,7 +8,10 @@ use bevy::{
     utils::HashSet,
 };
 
-use super::edge::{RoadEdge, Twist};
+use super::{
+    cross_section::{CrossSection, LaneKind, LaneProfile},
+    edge::{RoadEdge, Twist},
+};
 
 pub struct RoadGridPlugin;
 impl Plugin for RoadGridPlugin {
@@ -56,43 +59,128 @@ struct Curve {
     end: Vec2,
     radius: f32,
     length: f32,
-    lanes: u32,
+    /// Widths at the start and end, which differ where lanes are added or dropped
+    width: f32,
+    end_width: f32,
+    texture: u32,
+    /// Lanes of the curve are `lane_count` lanes from `first_lane` on in the lane buffer
+    first_lane: u32,
+    lane_count: u32,
+}
+
+/// Strip of a curve's cross-section, from left to right
+#[derive(ShaderType, Debug, Clone)]
+struct CurveLane {
+    kind: u32,
+    width: f32,
+    /// Where the lane starts across the driving lanes going the same way it lies among, which
+    /// share their markings, and how wide those are together
+    carriageway_start: f32,
+    carriageway_width: f32,
+    /// The same at the end of the curve
+    end_width: f32,
+    end_carriageway_start: f32,
+    end_carriageway_width: f32,
+    /// Whether lanes going the other way are on its left (1) or right (2)
+    dividers: u32,
+}
+
+impl CurveLane {
+    fn across(cross_section: &CrossSection) -> Vec<CurveLane> {
+        let lanes = &cross_section.lanes;
+        let opposing = |a: Option<&LaneProfile>, b: Option<&LaneProfile>| match (a, b) {
+            (Some(a), Some(b)) => driving(a) && driving(b) && a.direction != b.direction,
+            _ => false,
+        };
+
+        let start = Self::carriageways(lanes, 0.0);
+        let end = Self::carriageways(lanes, 1.0);
+        lanes
+            .iter()
+            .enumerate()
+            .map(|(index, lane)| {
+                let previous = index.checked_sub(1).map(|index| &lanes[index]);
+                let next = lanes.get(index + 1);
+
+                CurveLane {
+                    kind: lane.kind.index(),
+                    width: start[index].0,
+                    carriageway_start: start[index].1,
+                    carriageway_width: start[index].2,
+                    end_width: end[index].0,
+                    end_carriageway_start: end[index].1,
+                    end_carriageway_width: end[index].2,
+                    dividers: opposing(previous, Some(lane)) as u32
+                        | (opposing(Some(lane), next) as u32) << 1,
+                }
+            })
+            .collect()
+    }
+
+    /// Width of each lane at `along`, the fraction of the curve's length from its start, with
+    /// where it starts across its carriageway and how wide that is
+    fn carriageways(lanes: &[LaneProfile], along: f32) -> Vec<(f32, f32, f32)> {
+        let shared = |a: &LaneProfile, b: &LaneProfile| {
+            driving(a) && driving(b) && a.direction == b.direction
+        };
+
+        // Width of the carriageway right before the current lane
+        let mut driven = 0.0;
+        lanes
+            .iter()
+            .enumerate()
+            .map(|(index, lane)| {
+                if !index
+                    .checked_sub(1)
+                    .is_some_and(|previous| shared(&lanes[previous], lane))
+                {
+                    driven = 0.0;
+                }
+
+                let width = lane.width_at(along);
+                let ahead = lanes[index..]
+                    .windows(2)
+                    .take_while(|pair| shared(&pair[0], &pair[1]))
+                    .map(|pair| pair[1].width_at(along))
+                    .sum::<f32>();
+
+                let carriageway = (width, driven, driven + width + ahead);
+                driven += width;
+                carriageway
+            })
+            .collect()
+    }
 }
 
-impl From<&RoadEdge> for Curve {
-    fn from(edge: &RoadEdge) -> Self {
+fn driving(lane: &LaneProfile) -> bool {
+    lane.kind == LaneKind::Driving
+}
+
+impl Curve {
+    fn new(edge: &RoadEdge, first_lane: u32) -> Self {
         let rel_start = edge.start().translation.xz() - edge.center().xz();
         let rel_end = edge.end().translation.xz() - edge.center().xz();
+        let road_type = edge.road_type();
 
         // Use center and angle as start and end point for straight lines
-        match edge.twist() {
-            Twist::Clockwise => Self {
-                twist: 1,
-                center: edge.center().xz(),
-                start: rel_start,
-                end: rel_end,
-                radius: edge.radius(),
-                length: edge.length(),
-                lanes: edge.lanes() as u32,
-            },
-            Twist::CounterClockwise => Self {
-                twist: 0,
-                center: edge.center().xz(),
-                start: rel_start,
-                end: rel_end,
-                radius: edge.radius(),
-                length: edge.length(),
-                lanes: edge.lanes() as u32,
-            },
-            Twist::Straight => Self {
-                twist: 2,
-                center: edge.center().xz(),
-                start: rel_start,
-                end: rel_end,
-                radius: 0.0,
-                length: edge.length(),
-                lanes: edge.lanes() as u32,
-            },
+        let (twist, radius) = match edge.twist() {
+            Twist::Clockwise => (1, edge.radius()),
+            Twist::CounterClockwise => (0, edge.radius()),
+            Twist::Straight => (2, 0.0),
+        };
+
+        Self {
+            twist,
+            center: edge.center().xz(),
+            start: rel_start,
+            end: rel_end,
+            radius,
+            length: edge.length(),
+            width: road_type.cross_section.width_at(0.0),
+            end_width: road_type.cross_section.width_at(1.0),
+            texture: road_type.texture.index(),
+            first_lane,
+            lane_count: road_type.cross_section.lanes.len() as u32,
         }
     }
 }
@@ -105,6 +193,9 @@ struct WorldMaterial {
 
     #[storage(2, read_only)]
     pub curves: Vec<Curve>,
+
+    #[storage(3, read_only)]
+    pub lanes: Vec<CurveLane>,
 }
 
 impl Material for WorldMaterial {
@@ -146,6 +237,7 @@ fn init_world(
                     material: materials.add(WorldMaterial {
                         color_texture: Some(asset_server.load("textures/road.png")),
                         curves: Vec::new(),
+                        lanes: Vec::new(),
                     }),
                     ..default()
                 },
@@ -202,18 +294,23 @@ fn update_edge_of_tile(
 ) {
     for (entity, edge) in &changed_edges {
         for (mut tile, tile_aabb) in &mut tiles {
-            if tile.edges.contains(&entity) {
-                let tile_aabb3 = Aabb3d {
-                    min: tile_aabb.min().into(),
-                    max: tile_aabb.max().into(),
-                };
+            let tile_aabb3 = Aabb3d {
+                min: tile_aabb.min().into(),
+                max: tile_aabb.max().into(),
+            };
 
-                if !edge.aabb3().intersects(&tile_aabb3) {
+            // Edges that were moved or reshaped can reach into other tiles too
+            match edge.aabb3().intersects(&tile_aabb3) {
+                true => {
+                    tile.edges.insert(entity);
+                }
+                false if tile.edges.contains(&entity) => {
                     tile.edges.remove(&entity);
                 }
-
-                tile.dirty = true;
+                false => continue,
             }
+
+            tile.dirty = true;
         }
     }
 }
@@ -225,17 +322,18 @@ fn update_material(
 ) {
     for (handle, mut tile) in &mut changed_tiles {
         let mat = materials.get_mut(handle).unwrap();
-        mat.curves = tile
-            .edges
-            .iter()
-            .map(|entity| {
-                Curve::from(
-                    edges
-                        .get(*entity)
-                        .expect("World Tile has entity that is not a road edge"),
-                )
-            })
-            .collect();
+        mat.curves.clear();
+        mat.lanes.clear();
+
+        for entity in &tile.edges {
+            let edge = edges
+                .get(*entity)
+                .expect("World Tile has entity that is not a road edge");
+
+            mat.curves.push(Curve::new(edge, mat.lanes.len() as u32));
+            mat.lanes
+                .extend(CurveLane::across(&edge.road_type().cross_section));
+        }
 
         tile.dirty = false;
     }
@@ -0,0 +1,240 @@
+use std::time::Duration;
+
+use bevy::{
+    ecs::schedule::ScheduleLabel, input::common_conditions::input_just_released, prelude::*,
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::states::GameState;
+
+/// Rate at which the simulation advances, independent of the framerate
+pub const SIMULATION_HZ: f64 = 20.0;
+/// Seed of the simulation random number generator unless another one is inserted
+pub const DEFAULT_SEED: u64 = 0;
+/// Most steps taken in a single frame, so a slow frame at high speed can't snowball
+pub const MAX_STEPS_PER_FRAME: u32 = 64;
+/// Speed multipliers selected with the number keys
+pub const SPEEDS: [f32; 4] = [1.0, 2.0, 4.0, 16.0];
+
+pub struct SimulationClockPlugin;
+impl Plugin for SimulationClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_schedule(SimulationSchedule)
+            .register_type::<SimulationClock>()
+            .init_resource::<SimulationClock>()
+            .insert_resource(SimulationRng::new(DEFAULT_SEED));
+    }
+}
+
+/// Runs the clock in real time while simulating, with keys to pause, single-step and change
+/// the speed. Headless runs step the simulation themselves instead.
+pub struct SimulationControlsPlugin;
+impl Plugin for SimulationControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                toggle_pause.run_if(input_just_released(KeyCode::Space)),
+                request_step.run_if(input_just_released(KeyCode::Period)),
+                change_speed,
+                run_simulation,
+            )
+                .chain()
+                .run_if(in_state(GameState::Simulating)),
+        );
+    }
+}
+
+/// Schedule advancing the simulation by one timestep. It runs as many times per frame as the
+/// clock asks for, independent of the framerate.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimulationSchedule;
+
+/// Time in the simulation, which advances in fixed steps
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct SimulationClock {
+    pub timestep: Duration,
+    /// How many simulated seconds pass per real second
+    pub speed: f32,
+    pub paused: bool,
+    /// Steps still to take while paused
+    requested_steps: u32,
+    accumulated: Duration,
+    tick: u64,
+}
+
+impl Default for SimulationClock {
+    fn default() -> Self {
+        Self::from_hz(SIMULATION_HZ)
+    }
+}
+
+impl SimulationClock {
+    pub fn from_hz(hz: f64) -> Self {
+        Self {
+            timestep: Duration::from_secs_f64(1.0 / hz),
+            speed: 1.0,
+            paused: false,
+            requested_steps: 0,
+            accumulated: Duration::ZERO,
+            tick: 0,
+        }
+    }
+
+    pub fn timestep_seconds(&self) -> f32 {
+        self.timestep.as_secs_f32()
+    }
+
+    /// Number of steps taken so far
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Simulated time so far
+    pub fn elapsed(&self) -> Duration {
+        self.timestep.mul_f64(self.tick as f64)
+    }
+
+    /// Takes a single step, even while paused
+    pub fn step(&mut self) {
+        self.requested_steps += 1;
+    }
+
+    /// Number of steps to take for `delta` of real time passing
+    pub fn advance(&mut self, delta: Duration) -> u32 {
+        if self.paused {
+            return std::mem::take(&mut self.requested_steps);
+        }
+
+        self.accumulated += delta.mul_f32(self.speed);
+        let mut steps = std::mem::take(&mut self.requested_steps);
+        while self.accumulated >= self.timestep {
+            self.accumulated -= self.timestep;
+            steps += 1;
+        }
+
+        if steps > MAX_STEPS_PER_FRAME {
+            self.accumulated = Duration::ZERO;
+        }
+
+        steps.min(MAX_STEPS_PER_FRAME)
+    }
+}
+
+/// Random number generator of the simulation. Everything random in the simulation draws from
+/// it, so runs with the same seed and the same input play out identically.
+#[derive(Resource, Debug, Clone, Deref, DerefMut)]
+pub struct SimulationRng(ChaCha8Rng);
+
+impl SimulationRng {
+    pub fn new(seed: u64) -> Self {
+        Self(ChaCha8Rng::seed_from_u64(seed))
+    }
+}
+
+/// Runs the simulation schedule once for every step the clock takes this frame
+pub fn run_simulation(world: &mut World) {
+    let delta = world.resource::<Time>().delta();
+    let steps = world.resource_mut::<SimulationClock>().advance(delta);
+
+    for _ in 0..steps {
+        step_simulation(world);
+    }
+}
+
+/// Advances the simulation by a single timestep
+pub fn step_simulation(world: &mut World) {
+    world.run_schedule(SimulationSchedule);
+    world.resource_mut::<SimulationClock>().tick += 1;
+}
+
+fn toggle_pause(mut clock: ResMut<SimulationClock>) {
+    clock.paused = !clock.paused;
+}
+
+fn request_step(mut clock: ResMut<SimulationClock>) {
+    clock.paused = true;
+    clock.step();
+}
+
+fn change_speed(input: Res<ButtonInput<KeyCode>>, mut clock: ResMut<SimulationClock>) {
+    let keys = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+    ];
+
+    for (key, speed) in keys.into_iter().zip(SPEEDS) {
+        if input.just_released(key) {
+            clock.speed = speed;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use bevy::ecs::system::CommandQueue;
+
+    use super::*;
+    use crate::{
+        interop,
+        road::RoadNetworkPlugin,
+        simulation::{demand::DemandFile, vehicle::Vehicle, SimulationPlugin},
+    };
+
+    const STEPS: u64 = 600;
+
+    /// Steps a headless simulation of the junction example and returns the edge, lane and
+    /// position of every vehicle, with edges given by their index in the network file
+    fn run(seed: u64) -> Vec<(usize, u8, f32)> {
+        let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/networks");
+        let network = interop::load_network(&assets.join("junction.road.ron")).unwrap();
+        let demand = DemandFile::load(&assets.join("junction.demand.ron")).unwrap();
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins((RoadNetworkPlugin, SimulationPlugin))
+            .insert_resource(SimulationRng::new(seed))
+            .insert_resource(network.drive_side);
+        app.finish();
+        app.cleanup();
+
+        let mut queue = CommandQueue::default();
+        let entities = network
+            .spawn(&mut Commands::new(&mut queue, &app.world))
+            .unwrap();
+        queue.apply(&mut app.world);
+        app.insert_resource(demand.resolve(&entities.edges).unwrap());
+        app.update();
+
+        for _ in 0..STEPS {
+            step_simulation(&mut app.world);
+        }
+
+        let edge_index = |edge| entities.edges.iter().position(|e| *e == edge).unwrap();
+        let mut vehicles = app
+            .world
+            .query::<(Entity, &Vehicle)>()
+            .iter(&app.world)
+            .map(|(entity, vehicle)| (entity, vehicle.clone()))
+            .collect::<Vec<(Entity, Vehicle)>>();
+        vehicles.sort_by_key(|(entity, _)| *entity);
+
+        vehicles
+            .into_iter()
+            .map(|(_, vehicle)| (edge_index(vehicle.edge), vehicle.lane, vehicle.position))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_plays_out_identically() {
+        let first = run(DEFAULT_SEED);
+        assert!(!first.is_empty());
+        assert_eq!(first, run(DEFAULT_SEED));
+    }
+}
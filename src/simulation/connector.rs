@@ -0,0 +1,204 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::road::{
+    biarc::compute_biarc,
+    cross_section::DriveSide,
+    edge::RoadEdge,
+    graph::{EdgeNodes, GraphSystemSet, RoadGraph},
+    palette::RoadType,
+};
+
+use super::mobil::turn_lanes;
+
+/// Sine of the angle beyond which an edge leaving a junction turns towards the kerb
+const KERB_TURN_SINE: f32 = 0.1;
+
+pub struct LaneConnectorPlugin;
+impl Plugin for LaneConnectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LaneConnectors>()
+            .add_systems(PostUpdate, build_lane_connectors.after(GraphSystemSet));
+    }
+}
+
+/// Path through a junction from the end of a lane of one edge to the start of a lane of an
+/// edge leaving the same node
+#[derive(Debug, Clone)]
+pub struct LaneConnector {
+    pub from: Entity,
+    pub from_lane: u8,
+    pub to: Entity,
+    pub to_lane: u8,
+    /// Pair of arcs the path follows
+    curves: (RoadEdge, RoadEdge),
+}
+
+impl LaneConnector {
+    /// Connector from lane `from_lane` of `from` to lane `to_lane` of `to`, with lanes counted
+    /// as `side` decides
+    pub fn new(
+        (from, from_edge): (Entity, &RoadEdge),
+        from_lane: u8,
+        (to, to_edge): (Entity, &RoadEdge),
+        to_lane: u8,
+        side: DriveSide,
+    ) -> Self {
+        let start = from_edge.get_end_transform(Some(from_lane), side);
+        let end = to_edge.interpolate_lane(0.0, to_lane as i32, side);
+
+        Self {
+            from,
+            from_lane,
+            to,
+            to_lane,
+            curves: compute_biarc(start, end, RoadType::with_lanes(1)),
+        }
+    }
+
+    pub fn length(&self) -> f32 {
+        self.curves.0.length() + self.curves.1.length()
+    }
+
+    /// Point `length` along the connector
+    pub fn interpolate(&self, length: f32) -> Transform {
+        let (first, second) = &self.curves;
+        match length <= first.length() {
+            true => first.interpolate(length),
+            false => second.interpolate(length - first.length()),
+        }
+    }
+}
+
+/// Lane connectors of every junction, by the lane they start from
+#[derive(Resource, Debug, Default)]
+pub struct LaneConnectors {
+    connectors: HashMap<(Entity, u8), Vec<LaneConnector>>,
+}
+
+impl LaneConnectors {
+    /// Connectors leaving the end of lane `lane` of `edge`
+    pub fn from_lane(&self, edge: Entity, lane: u8) -> &[LaneConnector] {
+        self.connectors
+            .get(&(edge, lane))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Connector from lane `lane` of `edge` onto `to`
+    pub fn onto(&self, edge: Entity, lane: u8, to: Entity) -> Option<&LaneConnector> {
+        self.from_lane(edge, lane)
+            .iter()
+            .find(|connector| connector.to == to)
+    }
+
+    /// Length of the connector from lane `lane` of `edge` onto `to` and the lane it arrives on,
+    /// as [`LaneOccupancy::ahead`](crate::road::occupancy::LaneOccupancy::ahead) takes them
+    pub fn crossing(&self, edge: Entity, lane: u8, to: Entity) -> Option<(f32, u8)> {
+        self.onto(edge, lane, to)
+            .map(|connector| (connector.length(), connector.to_lane))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &LaneConnector> {
+        self.connectors.values().flatten()
+    }
+}
+
+/// Pairs of lanes of `edge` and of `next` that connect, with lanes counted as `side` decides.
+/// The lanes `turn_lanes` gives for the turn lead to the lanes of `next` in order. Where `next`
+/// has fewer lanes, several lanes lead to one, and where it has more, turns towards the kerb
+/// keep to the kerb and other turns to the middle of the road. Lanes dropped before the end of
+/// `edge` or added after the start of `next` are left out, and so are U-turns onto the other
+/// direction of a two-way road.
+pub fn lane_connections<'a>(
+    graph: &RoadGraph,
+    edges: impl Fn(Entity) -> Option<&'a RoadEdge>,
+    edge: Entity,
+    next: Entity,
+    side: DriveSide,
+) -> Vec<(u8, u8)> {
+    let (Some(from), Some(to)) = (edges(edge), edges(next)) else {
+        return Vec::new();
+    };
+    if graph.twin(&edges, edge) == Some(next) {
+        return Vec::new();
+    }
+
+    let turn = turn_lanes(graph, &edges, edge, next, side);
+    let continuing = from.lanes_open_at(from.length(), side);
+    let from_lanes = match turn.start.max(continuing.start)..turn.end.min(continuing.end) {
+        both if both.is_empty() => turn,
+        both => both,
+    };
+    let to_lanes = to.lanes_open_at(0.0, side);
+
+    let (count, to_count) = (from_lanes.len(), to_lanes.len());
+    if to_count == 0 {
+        return Vec::new();
+    }
+
+    let end = from.end();
+    let direction = *to.start().forward();
+    let kerb = direction.dot(*end.left()) * side.inner_sign() < -KERB_TURN_SINE;
+    let first = match kerb {
+        true => to_count.saturating_sub(count),
+        false => 0,
+    };
+
+    from_lanes
+        .enumerate()
+        .map(|(index, lane)| {
+            let to_lane = match count > to_count {
+                true => index * to_count / count,
+                false => first + index,
+            };
+            (lane, to_lanes.start + to_lane as u8)
+        })
+        .collect()
+}
+
+type ChangedEdges<'w, 's> =
+    Query<'w, 's, (), (Or<(Changed<RoadEdge>, Changed<EdgeNodes>)>, With<EdgeNodes>)>;
+
+/// Rebuilds the connectors of every junction whenever edges are added, changed or removed
+fn build_lane_connectors(
+    changed_edges: ChangedEdges,
+    mut removed_edges: RemovedComponents<EdgeNodes>,
+    edges: Query<&RoadEdge>,
+    graph: Res<RoadGraph>,
+    drive_side: Res<DriveSide>,
+    mut connectors: ResMut<LaneConnectors>,
+) {
+    let removed = removed_edges.read().count() > 0;
+    if changed_edges.is_empty() && !removed && !drive_side.is_changed() {
+        return;
+    }
+
+    let edge_lookup = |entity| edges.get(entity).ok();
+    connectors.connectors.clear();
+    for (edge, nodes) in graph.edges() {
+        let Ok(from_edge) = edges.get(edge) else {
+            continue;
+        };
+
+        for next in graph.outgoing(nodes.end) {
+            let Ok(to_edge) = edges.get(*next) else {
+                continue;
+            };
+
+            for (from_lane, to_lane) in
+                lane_connections(&graph, edge_lookup, edge, *next, *drive_side)
+            {
+                connectors
+                    .connectors
+                    .entry((edge, from_lane))
+                    .or_default()
+                    .push(LaneConnector::new(
+                        (edge, from_edge),
+                        from_lane,
+                        (*next, to_edge),
+                        to_lane,
+                        *drive_side,
+                    ));
+            }
+        }
+    }
+}
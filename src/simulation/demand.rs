@@ -0,0 +1,154 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::vehicle::{VehicleDispatch, TRUCK_SHARE};
+
+/// Traffic demand as stored on disk. Flows refer to edges by their index in the network file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DemandFile {
+    pub flows: Vec<FlowDefinition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlowDefinition {
+    pub from: usize,
+    pub to: usize,
+    pub vehicles_per_hour: f32,
+    #[serde(default = "default_truck_share")]
+    pub truck_share: f64,
+}
+
+fn default_truck_share() -> f64 {
+    TRUCK_SHARE
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DemandError {
+    /// A flow refers to an edge that does not exist
+    MissingEdge {
+        flow: usize,
+        edge: usize,
+    },
+    /// A flow sends no vehicles, or a number that is not finite, per hour
+    InvalidRate {
+        flow: usize,
+        vehicles_per_hour: f32,
+    },
+    /// The truck share of a flow lies outside 0 to 1
+    InvalidTruckShare {
+        flow: usize,
+        truck_share: f64,
+    },
+    Io(String),
+    /// The contents are not a valid RON demand file
+    Format(String),
+}
+
+impl std::fmt::Display for DemandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DemandError::MissingEdge { flow, edge } => {
+                write!(f, "flow {flow} refers to missing edge {edge}")
+            }
+            DemandError::InvalidRate {
+                flow,
+                vehicles_per_hour,
+            } => {
+                write!(
+                    f,
+                    "flow {flow} sends {vehicles_per_hour} vehicles per hour, which is not positive"
+                )
+            }
+            DemandError::InvalidTruckShare { flow, truck_share } => {
+                write!(
+                    f,
+                    "flow {flow} has truck share {truck_share}, which is not between 0 and 1"
+                )
+            }
+            DemandError::Io(error) | DemandError::Format(error) => f.write_str(error),
+        }
+    }
+}
+
+impl std::error::Error for DemandError {}
+
+impl DemandFile {
+    /// Reads a RON demand file
+    pub fn load(path: &Path) -> Result<Self, DemandError> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| DemandError::Io(format!("{}: {error}", path.display())))?;
+
+        ron::from_str(&text)
+            .map_err(|error| DemandError::Format(format!("{}: {error}", path.display())))
+    }
+
+    /// Turns the flows into demand on the spawned `edges`, given in network file order
+    pub fn resolve(&self, edges: &[Entity]) -> Result<Demand, DemandError> {
+        let flows = self
+            .flows
+            .iter()
+            .enumerate()
+            .map(|(index, flow)| {
+                let edge = |edge: usize| {
+                    edges
+                        .get(edge)
+                        .copied()
+                        .ok_or(DemandError::MissingEdge { flow: index, edge })
+                };
+
+                if !(flow.vehicles_per_hour.is_finite() && flow.vehicles_per_hour > 0.0) {
+                    return Err(DemandError::InvalidRate {
+                        flow: index,
+                        vehicles_per_hour: flow.vehicles_per_hour,
+                    });
+                }
+                // Also false for NaN, which `gen_bool` panics on like any share outside 0 to 1
+                if !(0.0..=1.0).contains(&flow.truck_share) {
+                    return Err(DemandError::InvalidTruckShare {
+                        flow: index,
+                        truck_share: flow.truck_share,
+                    });
+                }
+
+                Ok(Flow {
+                    from: edge(flow.from)?,
+                    to: edge(flow.to)?,
+                    timer: Timer::from_seconds(
+                        3600.0 / flow.vehicles_per_hour,
+                        TimerMode::Repeating,
+                    ),
+                    truck_share: flow.truck_share,
+                })
+            })
+            .collect::<Result<Vec<Flow>, DemandError>>()?;
+
+        Ok(Demand { flows })
+    }
+}
+
+/// Vehicles sent from one edge to another at a steady rate
+#[derive(Debug, Clone)]
+pub struct Flow {
+    pub from: Entity,
+    pub to: Entity,
+    pub timer: Timer,
+    pub truck_share: f64,
+}
+
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Demand {
+    pub flows: Vec<Flow>,
+}
+
+/// Sends off the vehicles of every flow that is due
+pub fn spawn_demand(mut demand: ResMut<Demand>, mut dispatch: VehicleDispatch) {
+    let timestep = dispatch.timestep();
+
+    for flow in &mut demand.flows {
+        for _ in 0..flow.timer.tick(timestep).times_finished_this_tick() {
+            dispatch.dispatch(flow.from, flow.to, flow.truck_share);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+
+/// Lowest speed a driver wants to drive at, whatever the speed limit
+pub const MIN_DESIRED_SPEED: f32 = 0.1;
+
+/// Parameters of the Intelligent Driver Model, which gives the acceleration of a vehicle from
+/// its own speed and the gap to the vehicle in front
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct Idm {
+    /// Speed the driver wants to drive at on a free road
+    pub desired_speed: f32,
+    /// Time gap the driver keeps to the leader
+    pub time_headway: f32,
+    /// Gap kept to the leader when standing still
+    pub minimum_gap: f32,
+    pub max_acceleration: f32,
+    pub comfortable_deceleration: f32,
+    /// How quickly acceleration drops off when nearing the desired speed
+    pub exponent: f32,
+}
+
+impl Default for Idm {
+    fn default() -> Self {
+        Self {
+            desired_speed: 5.0,
+            time_headway: 1.0,
+            minimum_gap: 0.5,
+            max_acceleration: 2.0,
+            comfortable_deceleration: 3.0,
+            exponent: 4.0,
+        }
+    }
+}
+
+/// The vehicle being followed, as seen from the follower
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Leader {
+    /// Bumper to bumper distance
+    pub gap: f32,
+    pub speed: f32,
+}
+
+impl Idm {
+    /// The same driver on a road where it may drive at most `limit`. Limits of zero or less
+    /// still let the driver creep along at `MIN_DESIRED_SPEED`, as the model divides by the
+    /// desired speed.
+    pub fn with_speed_limit(self, limit: f32) -> Self {
+        Self {
+            desired_speed: self.desired_speed.min(limit).max(MIN_DESIRED_SPEED),
+            ..self
+        }
+    }
+
+    pub fn acceleration(&self, speed: f32, leader: Option<Leader>) -> f32 {
+        let free_road = 1.0 - (speed / self.desired_speed).powf(self.exponent);
+
+        let Some(leader) = leader else {
+            return self.max_acceleration * free_road;
+        };
+
+        let approach = speed - leader.speed;
+        let desired_gap = self.minimum_gap
+            + (speed * self.time_headway
+                + speed * approach
+                    / (2.0 * (self.max_acceleration * self.comfortable_deceleration).sqrt()))
+            .max(0.0);
+
+        let interaction = (desired_gap / leader.gap.max(0.01)).powi(2);
+
+        self.max_acceleration * (free_road - interaction)
+    }
+
+    /// Advances the vehicle by one timestep, returning the distance driven and the new speed.
+    /// Vehicles brake to a stop rather than drive backwards.
+    pub fn step(&self, speed: f32, leader: Option<Leader>, timestep: f32) -> (f32, f32) {
+        let acceleration = self.acceleration(speed, leader);
+        let new_speed = (speed + acceleration * timestep).max(0.0);
+
+        let distance = match new_speed > 0.0 {
+            true => (speed + new_speed) * 0.5 * timestep,
+            // Only drive until standing still
+            false => speed * speed / (2.0 * -acceleration).max(f32::EPSILON),
+        };
+
+        (distance, new_speed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_limit_of_zero_keeps_acceleration_finite() {
+        let idm = Idm::default().with_speed_limit(0.0);
+
+        assert_eq!(idm.desired_speed, MIN_DESIRED_SPEED);
+        assert!(idm.acceleration(0.0, None).is_finite());
+        assert!(idm.acceleration(2.0, None) < 0.0);
+    }
+
+    #[test]
+    fn free_road_acceleration_vanishes_at_desired_speed() {
+        let idm = Idm::default();
+
+        assert_eq!(idm.acceleration(0.0, None), idm.max_acceleration);
+        assert!(idm.acceleration(idm.desired_speed * 0.5, None) > 0.0);
+        assert!(idm.acceleration(idm.desired_speed, None).abs() < 1e-6);
+        assert!(idm.acceleration(idm.desired_speed * 1.5, None) < 0.0);
+    }
+
+    #[test]
+    fn brakes_below_minimum_gap() {
+        let idm = Idm::default();
+
+        for speed in [0.0, 1.0, idm.desired_speed] {
+            let leader = Leader {
+                gap: idm.minimum_gap * 0.5,
+                speed,
+            };
+            assert!(idm.acceleration(speed, Some(leader)) < 0.0);
+        }
+    }
+
+    #[test]
+    fn step_never_goes_backwards() {
+        let idm = Idm::default();
+        let stopped = Leader {
+            gap: 0.1,
+            speed: 0.0,
+        };
+
+        for speed in [0.0, 0.5, 2.0, idm.desired_speed, 2.0 * idm.desired_speed] {
+            for timestep in [0.05, 0.5, 2.0] {
+                let (distance, new_speed) = idm.step(speed, Some(stopped), timestep);
+                assert!(distance >= 0.0, "{speed} over {timestep}: drove {distance}");
+                assert!(
+                    new_speed >= 0.0,
+                    "{speed} over {timestep}: sped to {new_speed}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use bevy::{prelude::*, utils::HashMap};
+
+/// Running totals of the traffic in the simulation
+#[derive(Resource, Debug, Clone, Default)]
+pub struct TrafficMetrics {
+    /// Vehicles that set off
+    pub departed: usize,
+    /// Vehicles that reached the end of their route
+    pub arrived: usize,
+    /// Summed travel time of the vehicles that arrived, in seconds
+    pub travel_time: f32,
+    pub edges: HashMap<Entity, EdgeMetrics>,
+}
+
+/// Totals of the vehicles that drove over an edge
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EdgeMetrics {
+    pub vehicles: usize,
+    /// Seconds spent on the edge
+    pub time: f32,
+    /// Seconds lost compared to driving at the desired speed
+    pub delay: f32,
+}
+
+impl TrafficMetrics {
+    pub fn depart(&mut self) {
+        self.departed += 1;
+    }
+
+    /// Counts a vehicle that left `edge` after `time` seconds, where it could have done it in
+    /// `free_flow` seconds on an empty road
+    pub fn leave_edge(&mut self, edge: Entity, time: f32, free_flow: f32) {
+        let metrics = self.edges.entry(edge).or_default();
+        metrics.vehicles += 1;
+        metrics.time += time;
+        metrics.delay += (time - free_flow).max(0.0);
+    }
+
+    pub fn arrive(&mut self, travel_time: f32) {
+        self.arrived += 1;
+        self.travel_time += travel_time;
+    }
+
+    pub fn mean_travel_time(&self) -> Option<f32> {
+        (self.arrived > 0).then(|| self.travel_time / self.arrived as f32)
+    }
+}
@@ -0,0 +1,241 @@
+use std::ops::Range;
+
+use bevy::prelude::*;
+
+use crate::road::{cross_section::DriveSide, edge::RoadEdge, graph::RoadGraph};
+
+/// Parameters of the MOBIL lane changing model (Minimizing Overall Braking Induced by Lane
+/// changes). A driver changes lanes when it gains enough acceleration, counting the loss of
+/// the vehicles it affects by its politeness, and the new follower does not need to brake too
+/// hard.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct Mobil {
+    /// How much the acceleration of others weighs against the driver's own, from 0 (selfish)
+    /// to 1 (altruistic)
+    pub politeness: f32,
+    /// Gain in acceleration needed before changing lanes, which prevents frantic switching
+    pub threshold: f32,
+    /// Hardest braking a lane change may force on the new follower
+    pub safe_deceleration: f32,
+}
+
+impl Default for Mobil {
+    fn default() -> Self {
+        Self {
+            politeness: 0.3,
+            threshold: 0.2,
+            safe_deceleration: 4.0,
+        }
+    }
+}
+
+/// Accelerations of the vehicles involved in a lane change. Vehicles that are not there count
+/// as not accelerating.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Accelerations {
+    pub own: f32,
+    /// Vehicle behind on the current lane
+    pub old_follower: f32,
+    /// Vehicle behind on the target lane
+    pub new_follower: f32,
+}
+
+impl Mobil {
+    /// Advantage of changing lanes, for the driver and, weighed by politeness, the others
+    pub fn incentive(&self, before: Accelerations, after: Accelerations) -> f32 {
+        let own = after.own - before.own;
+        let others =
+            (after.old_follower - before.old_follower) + (after.new_follower - before.new_follower);
+
+        own + self.politeness * others
+    }
+
+    /// Whether the new follower can brake for the vehicle cutting in
+    pub fn is_safe(&self, after: Accelerations) -> bool {
+        after.new_follower >= -self.safe_deceleration
+    }
+
+    /// Whether to change lanes. A `bias` is added to the incentive for changes the route asks
+    /// for.
+    pub fn should_change(&self, before: Accelerations, after: Accelerations, bias: f32) -> bool {
+        self.is_safe(after) && self.incentive(before, after) + bias > self.threshold
+    }
+}
+
+/// Lanes of `edge` that lead to `next`. The lanes are divided over the edges leaving the end
+/// of `edge`, from the sharpest turn across the opposing traffic of `side` on the inner lanes
+/// to the sharpest turn towards the kerb on the outer lanes. When there are more turns than
+/// lanes, turns share lanes.
+pub fn turn_lanes<'a>(
+    graph: &RoadGraph,
+    edges: impl Fn(Entity) -> Option<&'a RoadEdge>,
+    edge: Entity,
+    next: Entity,
+    side: DriveSide,
+) -> Range<u8> {
+    let Some(road_edge) = edges(edge) else {
+        return 0..0;
+    };
+    let lanes = road_edge.lanes() as usize;
+    let end = road_edge.end();
+
+    let mut turns = graph
+        .next_edges(edge)
+        .iter()
+        .filter_map(|entity| {
+            let direction = *edges(*entity)?.start().forward();
+            let angle = direction
+                .dot(*end.left())
+                .atan2(direction.dot(*end.forward()));
+            Some((*entity, angle * side.inner_sign()))
+        })
+        .collect::<Vec<(Entity, f32)>>();
+    turns.sort_by(|(a, a_angle), (b, b_angle)| b_angle.total_cmp(a_angle).then(a.cmp(b)));
+
+    let Some(index) = turns.iter().position(|(entity, _)| *entity == next) else {
+        return 0..lanes as u8;
+    };
+
+    let first = index * lanes / turns.len();
+    let last = ((index + 1) * lanes / turns.len()).max(first + 1);
+
+    first as u8..last as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::HashMap;
+
+    use super::*;
+    use crate::road::{graph::EdgeNodes, palette::RoadType};
+
+    #[test]
+    fn forced_braking_beyond_safe_deceleration_is_unsafe() {
+        let mobil = Mobil::default();
+        let after = |new_follower| Accelerations {
+            new_follower,
+            ..default()
+        };
+
+        assert!(mobil.is_safe(after(0.0)));
+        assert!(mobil.is_safe(after(-mobil.safe_deceleration)));
+        assert!(!mobil.is_safe(after(-mobil.safe_deceleration - 0.1)));
+    }
+
+    #[test]
+    fn changes_lanes_for_enough_gain() {
+        let mobil = Mobil::default();
+        let before = Accelerations {
+            own: -1.0,
+            ..default()
+        };
+        let gain = |own| Accelerations { own, ..before };
+
+        assert!(mobil.should_change(before, gain(0.0), 0.0));
+        assert!(!mobil.should_change(before, gain(-1.0 + mobil.threshold * 0.5), 0.0));
+        // Polite drivers weigh the braking they force on the new follower
+        let rude = Accelerations {
+            own: 0.0,
+            old_follower: 0.0,
+            new_follower: -3.0,
+        };
+        assert!(!mobil.should_change(before, rude, 0.0));
+        // Unless the change would force harder braking than is safe
+        let unsafe_change = Accelerations {
+            new_follower: -mobil.safe_deceleration - 1.0,
+            ..gain(1.0)
+        };
+        assert!(!mobil.should_change(before, unsafe_change, 0.0));
+    }
+
+    #[test]
+    fn bias_overrides_threshold_but_not_safety() {
+        let mobil = Mobil::default();
+        let before = Accelerations::default();
+        let slower = Accelerations {
+            own: -0.5,
+            ..default()
+        };
+
+        assert!(!mobil.should_change(before, slower, 0.0));
+        assert!(mobil.should_change(before, slower, 1.0));
+        assert!(!mobil.should_change(before, before, -1.0));
+
+        let unsafe_change = Accelerations {
+            new_follower: -mobil.safe_deceleration - 1.0,
+            ..default()
+        };
+        assert!(!mobil.should_change(before, unsafe_change, 10.0));
+    }
+
+    /// An edge driving north into a node, with edges leaving it west, north and east
+    fn crossroads(lanes: u8) -> (RoadGraph, HashMap<Entity, RoadEdge>, [Entity; 4]) {
+        let center = Vec3::ZERO;
+        let ends = [
+            Vec3::new(0.0, 0.0, 20.0),
+            Vec3::new(-20.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -20.0),
+            Vec3::new(20.0, 0.0, 0.0),
+        ];
+        let node = |index: u32| Entity::from_raw(100 + index);
+        let ids = [0, 1, 2, 3].map(Entity::from_raw);
+
+        let mut graph = RoadGraph::default();
+        let mut edges = HashMap::new();
+        for (index, end) in ends.into_iter().enumerate() {
+            let (start, end, nodes) = match index {
+                0 => (
+                    end,
+                    center,
+                    EdgeNodes {
+                        start: node(0),
+                        end: node(4),
+                    },
+                ),
+                _ => (
+                    center,
+                    end,
+                    EdgeNodes {
+                        start: node(4),
+                        end: node(index as u32),
+                    },
+                ),
+            };
+            let edge = RoadEdge::from_start_end(
+                Transform::from_translation(start).looking_at(end, Vec3::Y),
+                end,
+                RoadType::with_lanes(lanes),
+            );
+
+            graph.insert_edge(ids[index], nodes);
+            edges.insert(ids[index], edge);
+        }
+
+        (graph, edges, ids)
+    }
+
+    #[test]
+    fn turn_lanes_follow_drive_side() {
+        let (graph, edges, [incoming, west, north, east]) = crossroads(3);
+        let lanes = |next, side| turn_lanes(&graph, |e| edges.get(&e), incoming, next, side);
+
+        // Lane 0 lies next to the opposing traffic, so it takes the turn across it
+        assert_eq!(lanes(west, DriveSide::Right), 0..1);
+        assert_eq!(lanes(north, DriveSide::Right), 1..2);
+        assert_eq!(lanes(east, DriveSide::Right), 2..3);
+
+        assert_eq!(lanes(east, DriveSide::Left), 0..1);
+        assert_eq!(lanes(north, DriveSide::Left), 1..2);
+        assert_eq!(lanes(west, DriveSide::Left), 2..3);
+    }
+
+    #[test]
+    fn turns_share_lanes_when_there_are_too_few() {
+        let (graph, edges, [incoming, west, north, east]) = crossroads(1);
+        let lanes = |next| turn_lanes(&graph, |e| edges.get(&e), incoming, next, DriveSide::Right);
+
+        for next in [west, north, east] {
+            assert_eq!(lanes(next), 0..1);
+        }
+    }
+}
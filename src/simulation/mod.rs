@@ -0,0 +1,36 @@
+use bevy::prelude::*;
+
+use self::{
+    clock::{SimulationClockPlugin, SimulationSchedule},
+    connector::LaneConnectorPlugin,
+    demand::Demand,
+    metrics::TrafficMetrics,
+    vehicle::{Trip, Vehicle, VehiclePlugin, VehicleType},
+};
+
+pub mod clock;
+pub mod connector;
+pub mod demand;
+pub mod idm;
+pub mod metrics;
+pub mod mobil;
+pub mod vehicle;
+
+/// Traffic simulation, without any rendering or input, so it can run headless
+pub struct SimulationPlugin;
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Vehicle>()
+            .register_type::<VehicleType>()
+            .register_type::<Trip>()
+            .init_resource::<Demand>()
+            .init_resource::<TrafficMetrics>()
+            .add_plugins((SimulationClockPlugin, LaneConnectorPlugin, VehiclePlugin))
+            .configure_sets(SimulationSchedule, SimulationSystemSet);
+    }
+}
+
+/// Systems that advance the simulation by one step of the `SimulationClock`. In the app the
+/// clock only runs while simulating, so traffic pauses in build mode.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimulationSystemSet;
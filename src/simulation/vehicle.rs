@@ -0,0 +1,793 @@
+use std::{collections::VecDeque, iter, time::Duration};
+
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+use rand::Rng;
+
+use crate::road::{
+    cross_section::DriveSide,
+    edge::RoadEdge,
+    graph::RoadGraph,
+    occupancy::{LaneOccupancy, LanePosition, Lookahead},
+    placeholder::RoadPlaceholder,
+    route::{find_route, Distance, RoutePoint},
+    RoadSpawner,
+};
+
+use super::{
+    clock::{SimulationClock, SimulationRng, SimulationSchedule},
+    connector::{LaneConnector, LaneConnectors},
+    demand::spawn_demand,
+    idm::{Idm, Leader},
+    metrics::TrafficMetrics,
+    mobil::{turn_lanes, Accelerations, Mobil},
+    SimulationSystemSet,
+};
+
+/// How far ahead drivers look for a vehicle to follow
+pub const LOOKAHEAD: f32 = 50.0;
+/// Seconds it takes to move over to the next lane
+pub const LANE_CHANGE_DURATION: f32 = 1.5;
+/// Incentive added to lane changes the route needs, so they win over discretionary ones
+pub const MANDATORY_BIAS: f32 = 10.0;
+/// Share of spawned vehicles that are trucks
+pub const TRUCK_SHARE: f64 = 0.2;
+/// Seconds between the vehicles road spawners send off
+pub const SPAWN_INTERVAL: f32 = 2.0;
+
+pub struct VehiclePlugin;
+impl Plugin for VehiclePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(VehicleSpawner {
+            timer: Timer::from_seconds(SPAWN_INTERVAL, TimerMode::Repeating),
+        })
+        .add_systems(Update, (add_edge_transforms, place_vehicles))
+        .add_systems(
+            SimulationSchedule,
+            (
+                register_vehicles,
+                spawn_vehicles,
+                spawn_demand,
+                change_lanes,
+                drive_vehicles,
+            )
+                .chain()
+                .in_set(SimulationSystemSet),
+        );
+    }
+}
+
+/// Vehicle driving along an edge. The vehicle entity is a child of the edge it is on. Past the
+/// end of the edge, it follows the lane connector from its lane through the junction onto the
+/// next edge of its route.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Vehicle {
+    pub edge: Entity,
+    /// Distance driven along the edge, and on along the lane connector past its end
+    pub position: f32,
+    /// Lane the vehicle drives on, or is moving over to
+    pub lane: u8,
+    /// Sideways position in lanes, which trails `lane` while changing lanes
+    pub lateral: f32,
+    pub speed: f32,
+}
+
+impl Vehicle {
+    pub fn is_changing_lanes(&self) -> bool {
+        self.lateral != self.lane as f32
+    }
+
+    /// Lanes the vehicle takes up, two while it is changing lanes
+    pub fn occupied_lanes(&self) -> impl Iterator<Item = u8> {
+        let from = self.lateral.floor() as u8;
+        let to = self.lateral.ceil() as u8;
+
+        iter::once(from).chain((to != from).then_some(to))
+    }
+}
+
+/// Size and driving behaviour shared by a kind of vehicle
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct VehicleType {
+    pub length: f32,
+    pub idm: Idm,
+    pub mobil: Mobil,
+}
+
+impl VehicleType {
+    pub const CAR: Self = Self {
+        length: 0.8,
+        idm: Idm {
+            desired_speed: 5.0,
+            time_headway: 1.0,
+            minimum_gap: 0.5,
+            max_acceleration: 2.0,
+            comfortable_deceleration: 3.0,
+            exponent: 4.0,
+        },
+        mobil: Mobil {
+            politeness: 0.3,
+            threshold: 0.2,
+            safe_deceleration: 4.0,
+        },
+    };
+
+    pub const TRUCK: Self = Self {
+        length: 1.6,
+        idm: Idm {
+            desired_speed: 4.0,
+            time_headway: 1.5,
+            minimum_gap: 0.8,
+            max_acceleration: 1.0,
+            comfortable_deceleration: 2.0,
+            exponent: 4.0,
+        },
+        mobil: Mobil {
+            politeness: 0.5,
+            threshold: 0.3,
+            safe_deceleration: 3.0,
+        },
+    };
+}
+
+/// Edges the vehicle will drive after the one it is on. The vehicle is removed when it
+/// reaches the end of its route.
+#[derive(Component, Debug, Clone, Default)]
+pub struct VehicleRoute {
+    pub edges: VecDeque<Entity>,
+}
+
+#[derive(Resource)]
+struct VehicleSpawner {
+    timer: Timer,
+}
+
+/// When the vehicle set off and when it entered the edge it is on, in simulated seconds
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Trip {
+    pub departure: f32,
+    pub entered_edge: f32,
+}
+
+/// Spawns a vehicle at the start of `edge`, already driving as fast as it wants to there
+pub fn spawn_vehicle(
+    commands: &mut Commands,
+    (edge, road_edge): (Entity, &RoadEdge),
+    lane: u8,
+    vehicle_type: VehicleType,
+    route: VecDeque<Entity>,
+    departure: f32,
+) -> Entity {
+    commands
+        .spawn((
+            Name::new("Vehicle"),
+            SpatialBundle::default(),
+            Vehicle {
+                edge,
+                position: 0.0,
+                lane,
+                lateral: lane as f32,
+                speed: vehicle_type
+                    .idm
+                    .with_speed_limit(road_edge.road_type().speed_limit)
+                    .desired_speed,
+            },
+            vehicle_type,
+            VehicleRoute { edges: route },
+            Trip {
+                departure,
+                entered_edge: departure,
+            },
+        ))
+        .set_parent(edge)
+        .id()
+}
+
+/// Edges get a transform so vehicles can be parented to them
+fn add_edge_transforms(
+    edges: Query<Entity, (Added<RoadEdge>, Without<Transform>)>,
+    mut commands: Commands,
+) {
+    for entity in &edges {
+        commands.entity(entity).insert(SpatialBundle::default());
+    }
+}
+
+/// Claims the stretch of lane behind the front of every vehicle, on both lanes while it is
+/// changing lanes. Vehicles on a lane connector claim the stretch before the start of the lane
+/// they are crossing onto, and the end of their edge while their back is still on it.
+fn register_vehicles(
+    vehicles: Query<(Entity, &Vehicle, &VehicleType, &VehicleRoute)>,
+    edges: Query<&RoadEdge>,
+    connectors: Res<LaneConnectors>,
+    mut occupancy: ResMut<LaneOccupancy>,
+) {
+    occupancy.clear();
+
+    for (entity, vehicle, vehicle_type, route) in &vehicles {
+        let back = vehicle.position - vehicle_type.length;
+        let length = edges
+            .get(vehicle.edge)
+            .map_or(f32::INFINITY, RoadEdge::length);
+        let connector = route
+            .edges
+            .front()
+            .and_then(|next| connectors.onto(vehicle.edge, vehicle.lane, *next))
+            .filter(|_| vehicle.position > length);
+
+        if let Some(connector) = connector {
+            let front = vehicle.position - length - connector.length();
+            occupancy.register(
+                connector.to,
+                connector.to_lane,
+                entity,
+                front - vehicle_type.length,
+                front,
+            );
+
+            if back >= length {
+                continue;
+            }
+        }
+
+        for lane in vehicle.occupied_lanes() {
+            occupancy.register(vehicle.edge, lane, entity, back, vehicle.position);
+        }
+    }
+}
+
+/// Everything needed to send a vehicle on its way
+#[derive(SystemParam)]
+pub struct VehicleDispatch<'w, 's> {
+    clock: Res<'w, SimulationClock>,
+    rng: ResMut<'w, SimulationRng>,
+    graph: Res<'w, RoadGraph>,
+    occupancy: Res<'w, LaneOccupancy>,
+    metrics: ResMut<'w, TrafficMetrics>,
+    edges: Query<'w, 's, (Entity, &'static RoadEdge), Without<RoadPlaceholder>>,
+    commands: Commands<'w, 's>,
+}
+
+impl VehicleDispatch<'_, '_> {
+    pub fn timestep(&self) -> Duration {
+        self.clock.timestep
+    }
+
+    /// Routes a vehicle from the start of `from` to the end of `to` and spawns it on a random
+    /// lane. Nothing is spawned when there is no route, or no room at the start of the lane.
+    /// Routes leave out edges without driving lanes.
+    pub fn dispatch(&mut self, from: Entity, to: Entity, truck_share: f64) -> Option<Entity> {
+        let edge = |entity| {
+            self.edges
+                .get(entity)
+                .ok()
+                .map(|(_, edge)| edge)
+                .filter(|edge| edge.lanes() > 0)
+        };
+        let start = edge(from)?;
+        let lanes = start.lanes();
+
+        let route = find_route(
+            &self.graph,
+            edge,
+            RoutePoint::new(from, 0.0),
+            RoutePoint::new(to, edge(to)?.length()),
+            &Distance,
+        )?;
+
+        let lane = self.rng.gen_range(0..lanes);
+        let vehicle_type = match self.rng.gen_bool(truck_share) {
+            true => VehicleType::TRUCK,
+            false => VehicleType::CAR,
+        };
+
+        // Leave room to brake for whatever is near the start of the lane
+        let blocked = self
+            .occupancy
+            .ahead(
+                &self.graph,
+                edge,
+                LanePosition {
+                    edge: from,
+                    lane,
+                    length: 0.0,
+                },
+                vehicle_type.length + vehicle_type.idm.minimum_gap,
+                None,
+            )
+            .is_some();
+
+        if blocked {
+            return None;
+        }
+
+        self.metrics.depart();
+
+        let route = route.edges().skip(1).collect();
+        let departure = self.clock.elapsed().as_secs_f32();
+        Some(spawn_vehicle(
+            &mut self.commands,
+            (from, start),
+            lane,
+            vehicle_type,
+            route,
+            departure,
+        ))
+    }
+}
+
+/// Sends a vehicle down every edge leaving a road spawner, routed to a random dead end of the
+/// network
+fn spawn_vehicles(
+    mut spawner: ResMut<VehicleSpawner>,
+    road_spawners: Query<&Transform, With<RoadSpawner>>,
+    mut dispatch: VehicleDispatch,
+) {
+    if !spawner.timer.tick(dispatch.clock.timestep).just_finished() {
+        return;
+    }
+
+    let graph = &dispatch.graph;
+    let mut destinations = graph
+        .edges()
+        .filter(|(_, nodes)| graph.outgoing(nodes.end).is_empty())
+        .map(|(edge, _)| edge)
+        .collect::<Vec<Entity>>();
+    destinations.sort();
+
+    if destinations.is_empty() {
+        return;
+    }
+
+    for road_spawner in &road_spawners {
+        let starts = dispatch
+            .edges
+            .iter()
+            .filter(|(_, edge)| edge.start().translation.distance(road_spawner.translation) < 0.01)
+            .map(|(entity, _)| entity)
+            .collect::<Vec<Entity>>();
+
+        for start in starts {
+            let destination = destinations[dispatch.rng.gen_range(0..destinations.len())];
+            dispatch.dispatch(start, destination, TRUCK_SHARE);
+        }
+    }
+}
+
+/// Vehicle behind another, as seen from the vehicle in front
+struct Follower {
+    gap: f32,
+    speed: f32,
+    idm: Idm,
+}
+
+/// Lets every vehicle that is not already changing lanes or crossing a junction decide on a lane
+/// change with MOBIL.
+/// While the next edge of the route can only be reached from some lanes, vehicles never leave
+/// those lanes and vehicles outside them get a strong bias to move over. Lanes dropped along
+/// the edge never lead on, and vehicles only move onto lanes where they are open.
+fn change_lanes(
+    mut vehicles: Query<(Entity, &mut Vehicle, &VehicleType, &VehicleRoute)>,
+    edges: Query<&RoadEdge>,
+    graph: Res<RoadGraph>,
+    connectors: Res<LaneConnectors>,
+    occupancy: Res<LaneOccupancy>,
+    drive_side: Res<DriveSide>,
+) {
+    let edge_lookup = |entity| edges.get(entity).ok();
+    let crossing = |edge, lane, to| connectors.crossing(edge, lane, to);
+    let speed_limit = |entity| {
+        edges
+            .get(entity)
+            .map_or(f32::INFINITY, |edge| edge.road_type().speed_limit)
+    };
+
+    let leader = |entity, vehicle: &Vehicle, lane, route: &VehicleRoute| {
+        occupancy
+            .ahead_along_route(
+                &graph,
+                edge_lookup,
+                LanePosition {
+                    edge: vehicle.edge,
+                    lane,
+                    length: vehicle.position,
+                },
+                LOOKAHEAD,
+                Some(entity),
+                Lookahead {
+                    route: route.edges.iter().copied(),
+                    junction: crossing,
+                },
+            )
+            .map(|sighting| Leader {
+                gap: sighting.gap,
+                speed: vehicles
+                    .get(sighting.occupant.agent)
+                    .map_or(0.0, |(_, leader, _, _)| leader.speed),
+            })
+    };
+
+    let follower = |entity, vehicle: &Vehicle, lane, length| {
+        let sighting = occupancy.behind(
+            &graph,
+            edge_lookup,
+            LanePosition {
+                edge: vehicle.edge,
+                lane,
+                length: vehicle.position - length,
+            },
+            LOOKAHEAD,
+            Some(entity),
+        )?;
+        let (_, follower, follower_type, _) = vehicles.get(sighting.occupant.agent).ok()?;
+
+        Some(Follower {
+            gap: sighting.gap,
+            speed: follower.speed,
+            idm: follower_type
+                .idm
+                .with_speed_limit(speed_limit(follower.edge)),
+        })
+    };
+
+    let changes = vehicles
+        .iter()
+        .filter(|(_, vehicle, _, _)| !vehicle.is_changing_lanes())
+        .filter_map(|(entity, vehicle, vehicle_type, route)| {
+            let edge = edges.get(vehicle.edge).ok()?;
+            if vehicle.position > edge.length() {
+                return None;
+            }
+            let lanes = edge.lanes();
+            let wanted = match route.edges.front() {
+                Some(next) => turn_lanes(&graph, edge_lookup, vehicle.edge, *next, *drive_side),
+                None => 0..lanes,
+            };
+            let continuing = edge.lanes_open_at(edge.length(), *drive_side);
+            let wanted = match wanted.start.max(continuing.start)..wanted.end.min(continuing.end) {
+                both if both.is_empty() => wanted,
+                both => both,
+            };
+
+            let own = vehicle_type.idm.with_speed_limit(speed_limit(vehicle.edge));
+            let length = vehicle_type.length;
+            let current_leader = leader(entity, vehicle, vehicle.lane, route);
+            let old_follower = follower(entity, vehicle, vehicle.lane, length);
+
+            let targets = [
+                vehicle.lane.checked_sub(1),
+                Some(vehicle.lane + 1).filter(|lane| *lane < lanes),
+            ]
+            .map(|target| {
+                target.filter(|lane| {
+                    edge.lane_open(*lane, *drive_side)
+                        .contains(&vehicle.position)
+                })
+            });
+
+            targets
+                .into_iter()
+                .flatten()
+                .filter_map(|target| {
+                    let bias = match (wanted.contains(&vehicle.lane), wanted.contains(&target)) {
+                        (true, true) => 0.0,
+                        (true, false) => return None,
+                        (false, _) => {
+                            let towards = match target < vehicle.lane {
+                                true => wanted.end <= vehicle.lane,
+                                false => wanted.start > vehicle.lane,
+                            };
+                            match towards {
+                                true => MANDATORY_BIAS,
+                                false => return None,
+                            }
+                        }
+                    };
+
+                    let target_leader = leader(entity, vehicle, target, route);
+                    let new_follower = follower(entity, vehicle, target, length);
+
+                    // There is no room next to the vehicle
+                    if target_leader.is_some_and(|leader| leader.gap < 0.0)
+                        || new_follower
+                            .as_ref()
+                            .is_some_and(|follower| follower.gap < 0.0)
+                    {
+                        return None;
+                    }
+
+                    // Gap the followers get when the vehicle in between leaves or cuts in
+                    let closed_gap = |follower: &Follower, leader: Option<Leader>| {
+                        leader.map(|leader| Leader {
+                            gap: follower.gap + length + leader.gap,
+                            speed: leader.speed,
+                        })
+                    };
+                    let behind_vehicle = |follower: &Follower| Leader {
+                        gap: follower.gap,
+                        speed: vehicle.speed,
+                    };
+
+                    let mut before = Accelerations {
+                        own: own.acceleration(vehicle.speed, current_leader),
+                        ..default()
+                    };
+                    let mut after = Accelerations {
+                        own: own.acceleration(vehicle.speed, target_leader),
+                        ..default()
+                    };
+
+                    if let Some(follower) = &old_follower {
+                        before.old_follower = follower
+                            .idm
+                            .acceleration(follower.speed, Some(behind_vehicle(follower)));
+                        after.old_follower = follower
+                            .idm
+                            .acceleration(follower.speed, closed_gap(follower, current_leader));
+                    }
+
+                    if let Some(follower) = &new_follower {
+                        before.new_follower = follower
+                            .idm
+                            .acceleration(follower.speed, closed_gap(follower, target_leader));
+                        after.new_follower = follower
+                            .idm
+                            .acceleration(follower.speed, Some(behind_vehicle(follower)));
+                    }
+
+                    vehicle_type
+                        .mobil
+                        .should_change(before, after, bias)
+                        .then(|| (target, vehicle_type.mobil.incentive(before, after) + bias))
+                })
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(target, _)| (entity, target))
+        })
+        .collect::<Vec<(Entity, u8)>>();
+
+    for (entity, target) in changes {
+        if let Ok((_, mut vehicle, _, _)) = vehicles.get_mut(entity) {
+            vehicle.lane = target;
+        }
+    }
+}
+
+/// Where the lane of `vehicle` closes ahead of it, if it is dropped along its edge while
+/// another lane goes on. Vehicles that could not merge in time wait there.
+fn lane_end(vehicle: &Vehicle, edge: &RoadEdge, side: DriveSide) -> Option<f32> {
+    let end = edge.lane_open(vehicle.lane, side).end;
+    (end < edge.length()
+        && end >= vehicle.position
+        && !edge.lanes_open_at(edge.length(), side).is_empty())
+    .then_some(end)
+}
+
+/// Accelerates vehicles according to the vehicle in front, then moves them along their edge
+/// and on to the next edge of their route. Vehicles changing lanes follow whichever vehicle is
+/// closest on the lanes they take up, and vehicles in a dropped lane stop where it ends. Past
+/// the end of the edge, vehicles drive on along the lane connector from their lane.
+fn drive_vehicles(
+    clock: Res<SimulationClock>,
+    mut vehicles: Query<(
+        Entity,
+        &mut Vehicle,
+        &VehicleType,
+        &mut VehicleRoute,
+        &mut Trip,
+    )>,
+    edges: Query<&RoadEdge>,
+    (graph, connectors, drive_side): (Res<RoadGraph>, Res<LaneConnectors>, Res<DriveSide>),
+    occupancy: Res<LaneOccupancy>,
+    mut metrics: ResMut<TrafficMetrics>,
+    mut commands: Commands,
+) {
+    let now = clock.elapsed().as_secs_f32();
+    let crossing = |edge, lane, to| connectors.crossing(edge, lane, to);
+
+    let leaders = vehicles
+        .iter()
+        .map(|(entity, vehicle, _, route, _)| {
+            let leader = vehicle
+                .occupied_lanes()
+                .chain(iter::once(vehicle.lane))
+                .filter_map(|lane| {
+                    occupancy.ahead_along_route(
+                        &graph,
+                        |entity| edges.get(entity).ok(),
+                        LanePosition {
+                            edge: vehicle.edge,
+                            lane,
+                            length: vehicle.position,
+                        },
+                        LOOKAHEAD,
+                        Some(entity),
+                        Lookahead {
+                            route: route.edges.iter().copied(),
+                            junction: crossing,
+                        },
+                    )
+                })
+                .min_by(|a, b| a.gap.total_cmp(&b.gap))
+                .map(|sighting| Leader {
+                    gap: sighting.gap,
+                    speed: vehicles
+                        .get(sighting.occupant.agent)
+                        .map_or(0.0, |(_, leader, ..)| leader.speed),
+                });
+            let lane_end = edges
+                .get(vehicle.edge)
+                .ok()
+                .and_then(|edge| lane_end(vehicle, edge, *drive_side))
+                .map(|end| Leader {
+                    gap: end - vehicle.position,
+                    speed: 0.0,
+                });
+            let leader = leader
+                .into_iter()
+                .chain(lane_end)
+                .min_by(|a, b| a.gap.total_cmp(&b.gap));
+
+            (entity, leader)
+        })
+        .collect::<HashMap<Entity, Option<Leader>>>();
+
+    'vehicles: for (entity, mut vehicle, vehicle_type, mut route, mut trip) in &mut vehicles {
+        let leader = leaders.get(&entity).copied().flatten();
+        let idm = edges.get(vehicle.edge).map_or(vehicle_type.idm, |edge| {
+            vehicle_type
+                .idm
+                .with_speed_limit(edge.road_type().speed_limit)
+        });
+        let (distance, speed) = idm.step(vehicle.speed, leader, clock.timestep_seconds());
+
+        vehicle.position += distance;
+        vehicle.speed = speed;
+
+        let step = clock.timestep_seconds() / LANE_CHANGE_DURATION;
+        vehicle.lateral += (vehicle.lane as f32 - vehicle.lateral).clamp(-step, step);
+
+        let edge_entity = vehicle.edge;
+        loop {
+            let Ok(edge) = edges.get(vehicle.edge) else {
+                commands.entity(entity).despawn_recursive();
+                continue 'vehicles;
+            };
+
+            if vehicle.position <= edge.length() {
+                break;
+            }
+
+            let connector = route
+                .edges
+                .front()
+                .and_then(|next| connectors.onto(vehicle.edge, vehicle.lane, *next));
+            let through = edge.length() + connector.map_or(0.0, LaneConnector::length);
+            if vehicle.position <= through {
+                break;
+            }
+
+            let free_flow = through
+                / vehicle_type
+                    .idm
+                    .with_speed_limit(edge.road_type().speed_limit)
+                    .desired_speed;
+            metrics.leave_edge(vehicle.edge, now - trip.entered_edge, free_flow);
+            trip.entered_edge = now;
+
+            if route.edges.is_empty() {
+                metrics.arrive(now - trip.departure);
+            }
+
+            let Some(next) = route.edges.pop_front() else {
+                commands.entity(entity).despawn_recursive();
+                continue 'vehicles;
+            };
+
+            vehicle.position -= through;
+            vehicle.edge = next;
+
+            // Without a connector, the vehicle keeps its lane as far as the next edge has it
+            match connector.map(|connector| connector.to_lane) {
+                Some(lane) => {
+                    vehicle.lane = lane;
+                    vehicle.lateral = lane as f32;
+                }
+                None => match edges.get(next) {
+                    // Edges without driving lanes can't be driven on, so the vehicle leaves
+                    Ok(next_edge) if next_edge.lanes() == 0 => {
+                        commands.entity(entity).despawn_recursive();
+                        continue 'vehicles;
+                    }
+                    Ok(next_edge) => {
+                        vehicle.lane = vehicle.lane.min(next_edge.lanes().saturating_sub(1));
+                        vehicle.lateral = vehicle.lateral.min(vehicle.lane as f32);
+                    }
+                    Err(_) => {}
+                },
+            }
+        }
+
+        if vehicle.edge != edge_entity {
+            commands.entity(entity).set_parent(vehicle.edge);
+        }
+    }
+}
+
+fn place_vehicles(
+    mut vehicles: Query<(&Vehicle, &VehicleRoute, &mut Transform)>,
+    edges: Query<&RoadEdge>,
+    connectors: Res<LaneConnectors>,
+    drive_side: Res<DriveSide>,
+) {
+    for (vehicle, route, mut transform) in &mut vehicles {
+        let Ok(edge) = edges.get(vehicle.edge) else {
+            continue;
+        };
+
+        let connector = route
+            .edges
+            .front()
+            .and_then(|next| connectors.onto(vehicle.edge, vehicle.lane, *next));
+        if let Some(connector) = connector.filter(|_| vehicle.position > edge.length()) {
+            *transform = connector.interpolate(vehicle.position - edge.length());
+            continue;
+        }
+
+        // Ease in and out of lane changes
+        let from = vehicle.lateral.floor();
+        let t = vehicle.lateral - from;
+        let lateral = from + t * t * (3.0 - 2.0 * t);
+
+        *transform = edge.interpolate_lateral(vehicle.position, lateral, *drive_side);
+    }
+}
+
+pub struct VehicleMeshPlugin;
+impl Plugin for VehicleMeshPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_vehicle_mesh)
+            .add_systems(PostUpdate, attach_vehicle_meshes);
+    }
+}
+
+/// Meshes per vehicle length, so every vehicle type of the same size shares one mesh
+#[derive(Resource)]
+struct VehicleMesh {
+    meshes: HashMap<u32, Handle<Mesh>>,
+    material: Handle<StandardMaterial>,
+}
+
+fn setup_vehicle_mesh(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
+    commands.insert_resource(VehicleMesh {
+        meshes: HashMap::default(),
+        material: materials.add(Color::rgb(0.1, 0.3, 0.9)),
+    });
+}
+
+fn attach_vehicle_meshes(
+    vehicles: Query<(Entity, &VehicleType), Added<Vehicle>>,
+    mut vehicle_mesh: ResMut<VehicleMesh>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut commands: Commands,
+) {
+    for (entity, vehicle_type) in &vehicles {
+        let length = vehicle_type.length;
+        let mesh = vehicle_mesh
+            .meshes
+            .entry(length.to_bits())
+            .or_insert_with(|| {
+                // The vehicle position is its front, so the body extends backwards
+                let mesh = Cuboid::new(0.4, 0.3, length)
+                    .mesh()
+                    .translated_by(Vec3::new(0.0, 0.15, 0.5 * length));
+                meshes.add(mesh)
+            })
+            .clone();
+
+        commands
+            .entity(entity)
+            .insert((mesh, vehicle_mesh.material.clone()));
+    }
+}